@@ -7,6 +7,6 @@ fn main() {
         .expect("infallible");
 
     println!("{to_parse:#?}");
-    let mut stream: token_stream2::TokenStream = to_parse.into();
+    let stream: token_stream2::TokenStream = to_parse.into();
     println!("{stream:#?}");
 }
@@ -1,20 +1,365 @@
+#[cfg(feature = "proc-macro")]
+extern crate proc_macro;
+
 /// This enum represents a list of all valid tokens that procedural macros can parse.
 /// This is an abstraction over the system that `proc_macro` uses in it's TokenTree.
-#[derive(Clone, Debug, PartialEq)]
+///
+/// `Token` implements `Eq` and `Hash`, so it can be used in a `HashSet` or as a `HashMap` key.
+/// Floats are compared by their bit pattern, so every NaN is equal to every other NaN, and `0.0` is not equal to `-0.0`.
+/// ```
+/// use std::collections::HashSet;
+/// use token_stream2::Token;
+///
+/// let expected: HashSet<Token> = vec![Token::Comma, Token::Semi, Token::Float(f64::NAN)]
+///     .into_iter()
+///     .collect();
+/// assert!(expected.contains(&Token::Comma));
+/// assert!(expected.contains(&Token::Float(-f64::NAN)));
+/// assert!(!expected.contains(&Token::Colon));
+/// assert!(Token::Float(0.0) != Token::Float(-0.0));
+/// ```
+///
+/// With the `serde` feature enabled, `Token` implements `Serialize` and `Deserialize`.
+///
+/// `Token` is `#[non_exhaustive]`, since new tokens are added as more of Rust's syntax is recognized.
+/// A `match` on it outside of this crate needs a wildcard arm. Code that only needs to know what sort of token it has
+/// can match on `Token::as_category()` instead, which sorts every token, including future ones, into a few stable categories.
+/// ```
+/// use token_stream2::{Category, Token};
+///
+/// let describe = |token: &Token| match token {
+///     Token::Comma => "a comma",
+///     token => match token.as_category() {
+///         Category::Literal => "a literal",
+///         _ => "something else",
+///     },
+/// };
+/// assert!(describe(&Token::Comma) == "a comma");
+/// assert!(describe(&Token::Integer(1)) == "a literal");
+/// assert!(describe(&Token::Semi) == "something else");
+/// ```
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
 pub enum Token {
     /// Represents an identifier.
     Ident(String),
-    /// Represents a valid integer in normal or hexadecimal form.
+    /// Represents a raw identifier, such as `r#type`. The `r#` prefix is not included.
+    /// ```
+    /// let to_parse: proc_macro2::TokenStream = "r#fn".parse().expect("infallible");
+    ///
+    /// let mut stream: token_stream2::TokenStream = to_parse.into();
+    /// assert!(stream.next().unwrap().token() == &token_stream2::Token::RawIdent("fn".into()));
+    /// ```
+    RawIdent(String),
+    /// Represents `true` or `false`. This is only produced when `ConvertOptions::bool_literals` is enabled, otherwise they are identifiers.
+    Bool(bool),
+    /// Represents the wildcard `_`. This is only produced when `ConvertOptions::underscore` is enabled, otherwise it is an identifier.
+    /// Identifiers that merely start with an underscore, like `_x` or `__`, stay identifiers.
+    /// ```
+    /// use token_stream2::{ConvertOptions, Token, TokenStream};
+    ///
+    /// let to_parse: proc_macro2::TokenStream = "_ _x __".parse().expect("infallible");
+    ///
+    /// let options = ConvertOptions::new().underscore(true);
+    /// let stream = TokenStream::from_with_options(to_parse.clone(), options).unwrap();
+    /// let tokens: Vec<Token> = stream.clone().map(|t| t.token().clone()).collect();
+    /// assert!(tokens == vec![Token::Underscore, Token::Ident("_x".into()), Token::Ident("__".into())]);
+    /// assert!(proc_macro2::TokenStream::try_from(stream).unwrap().to_string() == "_ _x __");
+    ///
+    /// let stream: TokenStream = to_parse.into();
+    /// assert!(stream.peek_token(0) == Some(&Token::Ident("_".into())));
+    /// ```
+    Underscore,
+    /// Represents a lifetime or a label, such as `'a` or `'outer`. The leading `'` is not included.
+    /// ```
+    /// use token_stream2::Token;
+    ///
+    /// let to_parse: proc_macro2::TokenStream = "'static 'a 'outer: loop 'c'".parse().expect("infallible");
+    ///
+    /// let stream: token_stream2::TokenStream = to_parse.into();
+    /// let tokens: Vec<Token> = stream.map(|t| t.token().clone()).collect();
+    /// assert!(
+    ///     tokens
+    ///         == vec![
+    ///             Token::Lifetime("static".into()),
+    ///             Token::Lifetime("a".into()),
+    ///             Token::Lifetime("outer".into()),
+    ///             Token::Colon,
+    ///             Token::Ident("loop".into()),
+    ///             Token::Char('c'),
+    ///         ]
+    /// );
+    /// ```
+    Lifetime(String),
+    /// Represents a valid integer in normal, hexadecimal, binary, or octal form.
+    /// ```
+    /// use token_stream2::Token;
+    ///
+    /// let to_parse: proc_macro2::TokenStream = "0b1111 0o17".parse().expect("infallible");
+    ///
+    /// let stream: token_stream2::TokenStream = to_parse.into();
+    /// let tokens: Vec<Token> = stream.map(|t| t.token().clone()).collect();
+    /// assert!(tokens == vec![Token::Integer(15), Token::Integer(15)]);
+    ///
+    /// // Malformed digits are rejected before they ever reach this crate.
+    /// assert!("0b2".parse::<proc_macro2::TokenStream>().is_err());
+    /// ```
+    /// Hexadecimal literals produce a single token, with or without a suffix.
+    /// ```
+    /// use token_stream2::Token;
+    ///
+    /// let to_parse: proc_macro2::TokenStream = "0xFF 0x0 0x10u32".parse().expect("infallible");
+    ///
+    /// let stream: token_stream2::TokenStream = to_parse.into();
+    /// let tokens: Vec<Token> = stream.map(|t| t.token().clone()).collect();
+    /// assert!(
+    ///     tokens
+    ///         == vec![
+    ///             Token::Integer(255),
+    ///             Token::Integer(0),
+    ///             Token::IntegerSuffixed { value: 16, suffix: "u32".into() },
+    ///         ]
+    /// );
+    /// ```
+    /// Digit separators are allowed, so `1_000` is the integer 1000.
+    /// ```
+    /// use token_stream2::Token;
+    ///
+    /// let to_parse: proc_macro2::TokenStream = "1_000 1_000.5".parse().expect("infallible");
+    ///
+    /// let stream: token_stream2::TokenStream = to_parse.into();
+    /// let tokens: Vec<Token> = stream.map(|t| t.token().clone()).collect();
+    /// assert!(tokens == vec![Token::Integer(1000), Token::Float(1000.5)]);
+    /// ```
     Integer(i128),
+    /// Represents a valid unsuffixed integer that is too large for an `i128`. Suffixed ones are `Token::BigIntegerSuffixed`.
+    /// The literal is kept exactly as it was written, including any radix prefix and digit separators.
+    /// ```
+    /// use token_stream2::Token;
+    ///
+    /// let to_parse: proc_macro2::TokenStream = "170141183460469231731687303715884105728 0x1_0000_0000_0000_0000_0000_0000_0000_0000"
+    ///     .parse()
+    ///     .expect("infallible");
+    ///
+    /// let stream: token_stream2::TokenStream = to_parse.into();
+    /// let tokens: Vec<Token> = stream.map(|t| t.token().clone()).collect();
+    /// assert!(
+    ///     tokens
+    ///         == vec![
+    ///             Token::BigInteger("170141183460469231731687303715884105728".into()),
+    ///             Token::BigInteger("0x1_0000_0000_0000_0000_0000_0000_0000_0000".into()),
+    ///         ]
+    /// );
+    /// assert!(tokens[0].kind() == token_stream2::TokenKind::Integer);
+    /// ```
+    BigInteger(String),
     /// Represents a valid float.
+    /// Only the value is kept, so a float too large for an `f64`, like `1e400`, becomes infinity and renders as `inf`, which isn't source code.
+    /// Such a float can't be converted back into a `proc_macro2::TokenStream` either.
+    /// Use `ConvertOptions::float_text()` to get `Token::FloatLit` instead, which keeps the text the float was written as.
+    /// ```
+    /// use token_stream2::{ConvertError, ConvertOptions, Token, TokenStream};
+    ///
+    /// let to_parse: proc_macro2::TokenStream = "1e400".parse().expect("infallible");
+    ///
+    /// let stream: TokenStream = to_parse.clone().into();
+    /// assert!(stream[0].token() == &Token::Float(f64::INFINITY));
+    /// assert!(stream.to_string() == "inf");
+    /// assert!(matches!(proc_macro2::TokenStream::try_from(stream), Err(ConvertError::InvalidToken { .. })));
+    ///
+    /// let stream = TokenStream::from_with_options(to_parse, ConvertOptions::new().float_text(true)).unwrap();
+    /// assert!(stream.to_string() == "1e400");
+    /// ```
     Float(f64),
+    /// Represents a valid integer with a type suffix, such as `1u8` or `0xffu16`.
+    /// ```
+    /// use token_stream2::Token;
+    ///
+    /// let to_parse: proc_macro2::TokenStream = "1u8 0xffu16 2.5f32".parse().expect("infallible");
+    ///
+    /// let stream: token_stream2::TokenStream = to_parse.into();
+    /// let tokens: Vec<Token> = stream.map(|t| t.token().clone()).collect();
+    /// assert!(
+    ///     tokens
+    ///         == vec![
+    ///             Token::IntegerSuffixed { value: 1, suffix: "u8".into() },
+    ///             Token::IntegerSuffixed { value: 255, suffix: "u16".into() },
+    ///             Token::FloatSuffixed { value: 2.5, suffix: "f32".into() },
+    ///         ]
+    /// );
+    /// ```
+    IntegerSuffixed {
+        value: i128,
+        suffix: String,
+    },
+    /// Represents a valid integer with a type suffix that is too large for an `i128`, such as `u128::MAX` written as `340282366920938463463374607431768211455u128`.
+    /// Like `Token::BigInteger`, the digits are kept exactly as they were written, without the suffix.
+    /// The suffix is a `Box<str>` rather than a `String`, so this rare variant doesn't make every `Token` larger.
+    /// ```
+    /// use token_stream2::{Token, TokenKind};
+    ///
+    /// let to_parse: proc_macro2::TokenStream = "340282366920938463463374607431768211455u128 0xffff_ffff_ffff_ffff_ffff_ffff_ffff_ffffu128"
+    ///     .parse()
+    ///     .expect("infallible");
+    ///
+    /// let stream: token_stream2::TokenStream = to_parse.into();
+    /// let tokens: Vec<Token> = stream.clone().map(|t| t.token().clone()).collect();
+    /// assert!(
+    ///     tokens
+    ///         == vec![
+    ///             Token::BigIntegerSuffixed { digits: "340282366920938463463374607431768211455".into(), suffix: "u128".into() },
+    ///             Token::BigIntegerSuffixed { digits: "0xffff_ffff_ffff_ffff_ffff_ffff_ffff_ffff".into(), suffix: "u128".into() },
+    ///         ]
+    /// );
+    /// assert!(tokens[0].kind() == TokenKind::Integer);
+    /// assert!(tokens[0].to_string() == "340282366920938463463374607431768211455u128");
+    /// assert!(proc_macro2::TokenStream::try_from(stream).is_ok());
+    /// ```
+    BigIntegerSuffixed {
+        digits: String,
+        suffix: Box<str>,
+    },
+    /// Represents a valid float with a type suffix, such as `2.5f32`.
+    /// Like `Token::Float`, only the value is kept as an `f64`, so a float too large for an `f64` renders as `inf`.
+    FloatSuffixed {
+        value: f64,
+        suffix: String,
+    },
+    /// Represents a valid float, with or without a type suffix, along with the text it was written as.
+    /// This is only produced with `ConvertOptions::float_text()`, for code that has to emit floats exactly as they were written.
+    /// `repr` keeps the full literal, including any suffix, even when `value` has lost precision or overflowed to infinity.
+    /// ```
+    /// use token_stream2::{ConvertOptions, Token, TokenStream};
+    ///
+    /// let to_parse: proc_macro2::TokenStream = "0.1 1e400 2.50f32".parse().expect("infallible");
+    ///
+    /// let options = ConvertOptions::new().float_text(true);
+    /// let stream = TokenStream::from_with_options(to_parse, options).unwrap();
+    /// let tokens: Vec<Token> = stream.map(|t| t.token().clone()).collect();
+    /// assert!(
+    ///     tokens
+    ///         == vec![
+    ///             Token::FloatLit { value: 0.1, repr: "0.1".into() },
+    ///             Token::FloatLit { value: f64::INFINITY, repr: "1e400".into() },
+    ///             Token::FloatLit { value: 2.5, repr: "2.50f32".into() },
+    ///         ]
+    /// );
+    /// assert!(tokens[1].to_string() == "1e400");
+    /// ```
+    FloatLit {
+        value: f64,
+        repr: String,
+    },
     /// Represents a byte character. `b'a'`
-    ByteChar(char),
+    /// Escape sequences are decoded into the byte they stand for, including ones above `0x7F` like `b'\xff'`.
+    /// ```
+    /// use token_stream2::Token;
+    ///
+    /// let to_parse: proc_macro2::TokenStream = r"b'a' b'\n' b'\xff'".parse().expect("infallible");
+    ///
+    /// let stream: token_stream2::TokenStream = to_parse.into();
+    /// let tokens: Vec<Token> = stream.clone().map(|t| t.token().clone()).collect();
+    /// assert!(tokens == vec![Token::ByteChar(b'a'), Token::ByteChar(b'\n'), Token::ByteChar(0xff)]);
+    /// assert!(tokens[2].to_string() == r"b'\xff'");
+    /// assert!(proc_macro2::TokenStream::try_from(stream).unwrap().to_string() == r"b'a' b'\n' b'\xff'");
+    /// ```
+    ByteChar(u8),
     /// Represents a normal character. `'a'`
+    /// Escape sequences such as `'\n'` are decoded into the character they stand for.
+    /// ```
+    /// let to_parse: proc_macro2::TokenStream = r"'\n' '\'' '\x41' '\u{1F600}'"
+    ///     .parse()
+    ///     .expect("infallible");
+    ///
+    /// let stream: token_stream2::TokenStream = to_parse.into();
+    /// let tokens: Vec<token_stream2::Token> = stream.map(|t| t.token().clone()).collect();
+    /// assert!(
+    ///     tokens
+    ///         == vec![
+    ///             token_stream2::Token::Char('\n'),
+    ///             token_stream2::Token::Char('\''),
+    ///             token_stream2::Token::Char('A'),
+    ///             token_stream2::Token::Char('\u{1F600}'),
+    ///         ]
+    /// );
+    /// ```
     Char(char),
     /// Represents a byte string. `b"hello"`
+    /// ```
+    /// let to_parse: proc_macro2::TokenStream = r#"b"hi" "hi""#.parse().expect("infallible");
+    ///
+    /// let stream: token_stream2::TokenStream = to_parse.into();
+    /// let tokens: Vec<token_stream2::Token> = stream.map(|t| t.token().clone()).collect();
+    /// assert!(
+    ///     tokens
+    ///         == vec![
+    ///             token_stream2::Token::ByteString("hi".into()),
+    ///             token_stream2::Token::String("hi".into()),
+    ///         ]
+    /// );
+    /// ```
     ByteString(String),
+    /// Represents a raw string. `r#"hello"#`
+    /// The contents are kept verbatim, and `hashes` is the number of `#` on each side of the quotes.
+    /// ```
+    /// use token_stream2::Token;
+    ///
+    /// let to_parse: proc_macro2::TokenStream = r##"r"a\b" r#"x"y"#"##.parse().expect("infallible");
+    ///
+    /// let stream: token_stream2::TokenStream = to_parse.into();
+    /// let tokens: Vec<Token> = stream.map(|t| t.token().clone()).collect();
+    /// assert!(
+    ///     tokens
+    ///         == vec![
+    ///             Token::RawString { content: r"a\b".into(), hashes: 0 },
+    ///             Token::RawString { content: r#"x"y"#.into(), hashes: 1 },
+    ///         ]
+    /// );
+    /// ```
+    RawString {
+        content: String,
+        hashes: usize,
+    },
+    /// Represents a raw byte string. `br#"hello"#`
+    /// Like `Token::RawString`, the contents are kept verbatim, and `hashes` is the number of `#` on each side of the quotes.
+    /// ```
+    /// use token_stream2::{Token, TokenKind};
+    ///
+    /// let to_parse: proc_macro2::TokenStream = r##"br"a\b" br#"x"y"#"##.parse().expect("infallible");
+    ///
+    /// let stream: token_stream2::TokenStream = to_parse.into();
+    /// let tokens: Vec<Token> = stream.clone().map(|t| t.token().clone()).collect();
+    /// assert!(
+    ///     tokens
+    ///         == vec![
+    ///             Token::RawByteString { content: r"a\b".into(), hashes: 0 },
+    ///             Token::RawByteString { content: r#"x"y"#.into(), hashes: 1 },
+    ///         ]
+    /// );
+    /// assert!(tokens[0].kind() == TokenKind::ByteString);
+    /// assert!(proc_macro2::TokenStream::try_from(stream).unwrap().to_string() == r##"br"a\b" br#"x"y"#"##);
+    /// ```
+    RawByteString {
+        content: String,
+        hashes: usize,
+    },
     /// Represents a normal string. `"hello"`
+    /// The text between the surrounding quotes is kept verbatim.
+    /// ```
+    /// let to_parse: proc_macro2::TokenStream = r#""" "\"" "hello""#.parse().expect("infallible");
+    ///
+    /// let stream: token_stream2::TokenStream = to_parse.into();
+    /// let tokens: Vec<token_stream2::Token> = stream.map(|t| t.token().clone()).collect();
+    /// assert!(
+    ///     tokens
+    ///         == vec![
+    ///             token_stream2::Token::String("".into()),
+    ///             token_stream2::Token::String(r#"\""#.into()),
+    ///             token_stream2::Token::String("hello".into()),
+    ///         ]
+    /// );
+    /// ```
     String(String),
     /// Represents `+`.
     Plus,
@@ -48,6 +393,31 @@ pub enum Token {
     OpenParen,
     /// Represents `)`.
     CloseParen,
+    /// Represents the start of an invisible group, which has no delimiter in the source.
+    /// These are common in token streams produced by `macro_rules!` expansion.
+    /// ```
+    /// let inner: proc_macro2::TokenStream = "1 + 2".parse().expect("infallible");
+    /// let group = proc_macro2::Group::new(proc_macro2::Delimiter::None, inner);
+    /// let to_parse: proc_macro2::TokenStream =
+    ///     proc_macro2::TokenTree::Group(group).into();
+    ///
+    /// let stream: token_stream2::TokenStream = to_parse.into();
+    /// let tokens: Vec<token_stream2::Token> = stream.map(|t| t.token().clone()).collect();
+    /// assert!(
+    ///     tokens
+    ///         == vec![
+    ///             token_stream2::Token::OpenNone,
+    ///             token_stream2::Token::Integer(1),
+    ///             token_stream2::Token::Plus,
+    ///             token_stream2::Token::Integer(2),
+    ///             token_stream2::Token::CloseNone,
+    ///         ]
+    /// );
+    /// assert!(!tokens.contains(&token_stream2::Token::None));
+    /// ```
+    OpenNone,
+    /// Represents the end of an invisible group.
+    CloseNone,
     /// Represents `,`.
     Comma,
     /// Represents `'`.
@@ -74,239 +444,4733 @@ pub enum Token {
     Dollar,
     /// Represents `=`,
     Equal,
+    /// Represents `::`.
+    /// Multi-character operators are only produced when their characters are directly adjacent in the source,
+    /// so `a::b` produces `PathSep` while `a: :b` produces two `Colon`s.
+    /// Note that this also applies inside generics, so the end of `Vec<Vec<u8>>` is a `Shr`.
+    /// ```
+    /// use token_stream2::Token;
+    ///
+    /// let to_parse: proc_macro2::TokenStream = "a::b a -> b a == b".parse().expect("infallible");
+    ///
+    /// let stream: token_stream2::TokenStream = to_parse.into();
+    /// let tokens: Vec<Token> = stream.map(|t| t.token().clone()).collect();
+    /// assert!(tokens[1] == Token::PathSep);
+    /// assert!(tokens[4] == Token::RArrow);
+    /// assert!(tokens[7] == Token::EqEq);
+    /// assert!(tokens.len() == 9);
+    /// ```
+    PathSep,
+    /// Represents `->`.
+    RArrow,
+    /// Represents `=>`.
+    FatArrow,
+    /// Represents `==`.
+    EqEq,
+    /// Represents `!=`.
+    Ne,
+    /// Represents `<=`.
+    Le,
+    /// Represents `>=`.
+    Ge,
+    /// Represents `&&`.
+    AndAnd,
+    /// Represents `||`.
+    OrOr,
+    /// Represents `<<`.
+    Shl,
+    /// Represents `>>`.
+    Shr,
+    /// Represents a punctuation character that has no token of its own.
+    /// `proc_macro2` doesn't produce any such characters today, but this keeps the conversion from failing if it ever does.
+    /// Since `proc_macro2` can't represent these characters either, converting one back into a `proc_macro2::TokenStream` fails.
+    /// ```
+    /// use token_stream2::{ConvertError, Token, TokenStream};
+    ///
+    /// let token = Token::UnknownPunct('\\');
+    /// assert!(token.is_punct());
+    /// assert!(token.to_string() == "\\");
+    ///
+    /// let stream: TokenStream = vec![Token::Ident("a".into()), token].into_iter().collect();
+    /// let err = proc_macro2::TokenStream::try_from(stream).unwrap_err();
+    /// assert!(matches!(err, ConvertError::InvalidToken { index: 1, .. }));
+    /// ```
+    UnknownPunct(char),
     /// Represents no token.
+    /// This is reserved for streams built by hand: converting from `proc_macro2` never produces it, not even for
+    /// invisible groups, which use `Token::OpenNone` and `Token::CloseNone`. Use `TokenStream::compact()` to remove it.
+    /// ```
+    /// use proc_macro2::{Delimiter, Group, TokenTree};
+    ///
+    /// let mut source: proc_macro2::TokenStream = r#"
+    ///     #[derive(Debug)]
+    ///     pub struct Point<'a> { x: i32, y: &'a str }
+    ///     fn main() -> Result<(), ()> { let p = Point { x: -1, y: "\n" }; println!("{:?}", p); Ok(()) }
+    /// "#
+    /// .parse()
+    /// .expect("infallible");
+    /// source.extend([TokenTree::Group(Group::new(Delimiter::None, "1 + 2".parse().unwrap()))]);
+    ///
+    /// let stream: token_stream2::TokenStream = source.into();
+    /// assert!(stream.iter().all(|token| token.token() != &token_stream2::Token::None));
+    /// assert!(stream.iter().any(|token| token.token() == &token_stream2::Token::OpenNone));
+    /// ```
     None,
     /// Represents an uncategorizable literal.
     Literal(String),
 }
 
-/// This is an equivalent to the `Token` type with a span attached. Use `SpannedToken::span()` to retrieve it's span, and `SpannedToken::token()` to retreieve it's token.
-/// Note that this type is read-only, you ideally should not mutate it.
-#[derive(Clone, Debug)]
-pub struct SpannedToken {
-    token: Token,
-    span: proc_macro2::Span,
+/// This enum represents the reserved keywords of Rust, including the ones reserved for future use.
+/// Use `Token::as_keyword()` to classify an identifier.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Keyword {
+    /// Represents `as`.
+    As,
+    /// Represents `async`.
+    Async,
+    /// Represents `await`.
+    Await,
+    /// Represents `break`.
+    Break,
+    /// Represents `const`.
+    Const,
+    /// Represents `continue`.
+    Continue,
+    /// Represents `crate`.
+    Crate,
+    /// Represents `dyn`.
+    Dyn,
+    /// Represents `else`.
+    Else,
+    /// Represents `enum`.
+    Enum,
+    /// Represents `extern`.
+    Extern,
+    /// Represents `false`.
+    False,
+    /// Represents `fn`.
+    Fn,
+    /// Represents `for`.
+    For,
+    /// Represents `if`.
+    If,
+    /// Represents `impl`.
+    Impl,
+    /// Represents `in`.
+    In,
+    /// Represents `let`.
+    Let,
+    /// Represents `loop`.
+    Loop,
+    /// Represents `match`.
+    Match,
+    /// Represents `mod`.
+    Mod,
+    /// Represents `move`.
+    Move,
+    /// Represents `mut`.
+    Mut,
+    /// Represents `pub`.
+    Pub,
+    /// Represents `ref`.
+    Ref,
+    /// Represents `return`.
+    Return,
+    /// Represents `self`.
+    SelfValue,
+    /// Represents `Self`.
+    SelfType,
+    /// Represents `static`.
+    Static,
+    /// Represents `struct`.
+    Struct,
+    /// Represents `super`.
+    Super,
+    /// Represents `trait`.
+    Trait,
+    /// Represents `true`.
+    True,
+    /// Represents `type`.
+    Type,
+    /// Represents `unsafe`.
+    Unsafe,
+    /// Represents `use`.
+    Use,
+    /// Represents `where`.
+    Where,
+    /// Represents `while`.
+    While,
+    /// Represents `abstract`.
+    Abstract,
+    /// Represents `become`.
+    Become,
+    /// Represents `box`.
+    Box,
+    /// Represents `do`.
+    Do,
+    /// Represents `final`.
+    Final,
+    /// Represents `macro`.
+    Macro,
+    /// Represents `override`.
+    Override,
+    /// Represents `priv`.
+    Priv,
+    /// Represents `try`.
+    Try,
+    /// Represents `typeof`.
+    Typeof,
+    /// Represents `unsized`.
+    Unsized,
+    /// Represents `virtual`.
+    Virtual,
+    /// Represents `yield`.
+    Yield,
 }
 
-impl SpannedToken {
-    /// This function allows you to get the internal `Token` of a `SpannedToken`.
+const KEYWORDS: [(&str, Keyword); 51] = [
+    ("as", Keyword::As),
+    ("async", Keyword::Async),
+    ("await", Keyword::Await),
+    ("break", Keyword::Break),
+    ("const", Keyword::Const),
+    ("continue", Keyword::Continue),
+    ("crate", Keyword::Crate),
+    ("dyn", Keyword::Dyn),
+    ("else", Keyword::Else),
+    ("enum", Keyword::Enum),
+    ("extern", Keyword::Extern),
+    ("false", Keyword::False),
+    ("fn", Keyword::Fn),
+    ("for", Keyword::For),
+    ("if", Keyword::If),
+    ("impl", Keyword::Impl),
+    ("in", Keyword::In),
+    ("let", Keyword::Let),
+    ("loop", Keyword::Loop),
+    ("match", Keyword::Match),
+    ("mod", Keyword::Mod),
+    ("move", Keyword::Move),
+    ("mut", Keyword::Mut),
+    ("pub", Keyword::Pub),
+    ("ref", Keyword::Ref),
+    ("return", Keyword::Return),
+    ("self", Keyword::SelfValue),
+    ("Self", Keyword::SelfType),
+    ("static", Keyword::Static),
+    ("struct", Keyword::Struct),
+    ("super", Keyword::Super),
+    ("trait", Keyword::Trait),
+    ("true", Keyword::True),
+    ("type", Keyword::Type),
+    ("unsafe", Keyword::Unsafe),
+    ("use", Keyword::Use),
+    ("where", Keyword::Where),
+    ("while", Keyword::While),
+    ("abstract", Keyword::Abstract),
+    ("become", Keyword::Become),
+    ("box", Keyword::Box),
+    ("do", Keyword::Do),
+    ("final", Keyword::Final),
+    ("macro", Keyword::Macro),
+    ("override", Keyword::Override),
+    ("priv", Keyword::Priv),
+    ("try", Keyword::Try),
+    ("typeof", Keyword::Typeof),
+    ("unsized", Keyword::Unsized),
+    ("virtual", Keyword::Virtual),
+    ("yield", Keyword::Yield),
+];
+
+impl Keyword {
+    /// This function returns the source spelling of a keyword.
     /// ```
-    /// let to_parse: proc_macro2::TokenStream = r#"
-    ///      fn main() {
-    ///          println!("Hello world!");
-    ///      }
-    ///  "#
-    ///  .parse()
-    ///  .expect("infallible");
-    ///
-    /// let mut stream: token_stream2::TokenStream = to_parse.into();
-    /// assert!(stream.peek(2).unwrap().token() == &token_stream2::Token::OpenParen);
-    /// assert!(stream.peek(3).unwrap().token() == &token_stream2::Token::CloseParen);
+    /// assert!(token_stream2::Keyword::SelfType.as_str() == "Self");
     /// ```
-    pub fn token(&self) -> &Token {
-        &self.token
+    pub fn as_str(self) -> &'static str {
+        KEYWORDS
+            .iter()
+            .find(|(_, keyword)| *keyword == self)
+            .map(|(name, _)| *name)
+            .expect("infallible - every keyword is in the table")
     }
-    /// This function allows you to get the span of a SpannedToken.
-    /// An example was failed to be provided for this function, sorry.
-    pub fn span(&self) -> &proc_macro2::Span {
-        &self.span
+}
+
+// This function returns the bits a float is compared and hashed by, with every NaN treated as the same value.
+fn float_bits(value: f64) -> u64 {
+    if value.is_nan() {
+        f64::NAN.to_bits()
+    } else {
+        value.to_bits()
     }
 }
 
-#[derive(Clone, Debug)]
-pub struct TokenStream {
-    tokens: Vec<SpannedToken>,
-    iter_ptr: usize,
+impl PartialEq for Token {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Token::Ident(a), Token::Ident(b)) => a == b,
+            (Token::RawIdent(a), Token::RawIdent(b)) => a == b,
+            (Token::Bool(a), Token::Bool(b)) => a == b,
+            (Token::Lifetime(a), Token::Lifetime(b)) => a == b,
+            (Token::Integer(a), Token::Integer(b)) => a == b,
+            (Token::BigInteger(a), Token::BigInteger(b)) => a == b,
+            (
+                Token::BigIntegerSuffixed { digits, suffix },
+                Token::BigIntegerSuffixed {
+                    digits: other_digits,
+                    suffix: other_suffix,
+                },
+            ) => digits == other_digits && suffix == other_suffix,
+            (Token::Float(a), Token::Float(b)) => float_bits(*a) == float_bits(*b),
+            (
+                Token::IntegerSuffixed { value, suffix },
+                Token::IntegerSuffixed {
+                    value: other_value,
+                    suffix: other_suffix,
+                },
+            ) => value == other_value && suffix == other_suffix,
+            (
+                Token::FloatSuffixed { value, suffix },
+                Token::FloatSuffixed {
+                    value: other_value,
+                    suffix: other_suffix,
+                },
+            ) => float_bits(*value) == float_bits(*other_value) && suffix == other_suffix,
+            (
+                Token::FloatLit { value, repr },
+                Token::FloatLit {
+                    value: other_value,
+                    repr: other_repr,
+                },
+            ) => float_bits(*value) == float_bits(*other_value) && repr == other_repr,
+            (Token::ByteChar(a), Token::ByteChar(b)) => a == b,
+            (Token::Char(a), Token::Char(b)) => a == b,
+            (Token::UnknownPunct(a), Token::UnknownPunct(b)) => a == b,
+            (Token::ByteString(a), Token::ByteString(b)) => a == b,
+            (
+                Token::RawString { content, hashes },
+                Token::RawString {
+                    content: other_content,
+                    hashes: other_hashes,
+                },
+            )
+            | (
+                Token::RawByteString { content, hashes },
+                Token::RawByteString {
+                    content: other_content,
+                    hashes: other_hashes,
+                },
+            ) => content == other_content && hashes == other_hashes,
+            (Token::String(a), Token::String(b)) => a == b,
+            (Token::Literal(a), Token::Literal(b)) => a == b,
+            // Every remaining variant carries no data, so they are equal if they are the same variant.
+            _ => std::mem::discriminant(self) == std::mem::discriminant(other),
+        }
+    }
 }
 
-impl Iterator for TokenStream {
-    type Item = SpannedToken;
+impl Eq for Token {}
 
-    fn next(&mut self) -> Option<Self::Item> {
-        let x = self.tokens.get(self.iter_ptr).cloned();
-        self.iter_ptr += 1;
-        x
+impl std::hash::Hash for Token {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        std::mem::discriminant(self).hash(state);
+        match self {
+            Token::Ident(name) | Token::RawIdent(name) | Token::Lifetime(name) => name.hash(state),
+            Token::Bool(value) => value.hash(state),
+            Token::Integer(value) => value.hash(state),
+            Token::Float(value) => float_bits(*value).hash(state),
+            Token::IntegerSuffixed { value, suffix } => {
+                value.hash(state);
+                suffix.hash(state);
+            }
+            Token::FloatSuffixed { value, suffix } => {
+                float_bits(*value).hash(state);
+                suffix.hash(state);
+            }
+            Token::FloatLit { value, repr } => {
+                float_bits(*value).hash(state);
+                repr.hash(state);
+            }
+            Token::ByteChar(value) => value.hash(state),
+            Token::Char(value) | Token::UnknownPunct(value) => value.hash(state),
+            Token::RawString { content, hashes } | Token::RawByteString { content, hashes } => {
+                content.hash(state);
+                hashes.hash(state);
+            }
+            Token::BigIntegerSuffixed { digits, suffix } => {
+                digits.hash(state);
+                suffix.hash(state);
+            }
+            Token::BigInteger(text)
+            | Token::ByteString(text)
+            | Token::String(text)
+            | Token::Literal(text) => text.hash(state),
+            _ => {}
+        }
     }
 }
 
-impl TokenStream {
-    /// This function allows you to peek ahead in the tokenstream's iterator.
-    /// Useful for quickly parsing what's next.
+impl std::fmt::Display for Token {
+    /// This function renders a token the way it would be spelled in source code.
+    /// Invisible delimiters and `Token::None` render as nothing.
     /// ```
-    /// let to_parse: proc_macro2::TokenStream = r#"
-    ///      fn main() {
-    ///          println!("Hello world!");
-    ///      }
-    ///  "#
-    ///  .parse()
-    ///  .expect("infallible");
+    /// use token_stream2::Token;
     ///
-    /// let mut stream: token_stream2::TokenStream = to_parse.into();
-    /// assert!(stream.peek(2).unwrap().token() == &token_stream2::Token::OpenParen);
-    /// assert!(stream.peek(3).unwrap().token() == &token_stream2::Token::CloseParen);
+    /// assert!(Token::Plus.to_string() == "+");
+    /// assert!(Token::Ident("fn".into()).to_string() == "fn");
+    /// assert!(Token::Integer(42).to_string() == "42");
+    /// assert!(Token::String("hi".into()).to_string() == "\"hi\"");
+    /// assert!(Token::Char('\n').to_string() == "'\\n'");
     /// ```
-    pub fn peek(&mut self, ahead: usize) -> Option<SpannedToken> {
-        self.tokens.get(self.iter_ptr + ahead).cloned()
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(text) = punct_str(self) {
+            return f.write_str(text);
+        }
+        match self {
+            Token::Ident(name) => f.write_str(name),
+            Token::RawIdent(name) => write!(f, "r#{}", name),
+            Token::Bool(value) => write!(f, "{}", value),
+            Token::Underscore => f.write_str("_"),
+            Token::Lifetime(name) => write!(f, "'{}", name),
+            Token::Integer(value) => write!(f, "{}", value),
+            Token::BigInteger(digits) => f.write_str(digits),
+            Token::BigIntegerSuffixed { digits, suffix } => write!(f, "{}{}", digits, suffix),
+            // `Debug` keeps the decimal point, so the float doesn't turn into an integer.
+            Token::Float(value) => write!(f, "{:?}", value),
+            Token::IntegerSuffixed { value, suffix } => write!(f, "{}{}", value, suffix),
+            Token::FloatSuffixed { value, suffix } => write!(f, "{:?}{}", value, suffix),
+            Token::FloatLit { repr, .. } => f.write_str(repr),
+            Token::ByteChar(value) => write!(f, "b'{}'", std::ascii::escape_default(*value)),
+            Token::Char(value) => write!(f, "'{}'", value.escape_debug()),
+            Token::ByteString(content) => write!(f, "b\"{}\"", content),
+            Token::RawString { content, hashes } => {
+                let hashes = "#".repeat(*hashes);
+                write!(f, "r{}\"{}\"{}", hashes, content, hashes)
+            }
+            Token::RawByteString { content, hashes } => {
+                let hashes = "#".repeat(*hashes);
+                write!(f, "br{}\"{}\"{}", hashes, content, hashes)
+            }
+            Token::String(content) => write!(f, "\"{}\"", content),
+            Token::OpenBrace => f.write_str("{"),
+            Token::CloseBrace => f.write_str("}"),
+            Token::OpenBracket => f.write_str("["),
+            Token::CloseBracket => f.write_str("]"),
+            Token::OpenParen => f.write_str("("),
+            Token::CloseParen => f.write_str(")"),
+            Token::OpenNone | Token::CloseNone | Token::None => Ok(()),
+            Token::Literal(text) => f.write_str(text),
+            Token::UnknownPunct(ch) => write!(f, "{}", ch),
+            _ => unreachable!("punctuation is rendered by punct_str"),
+        }
     }
 }
-impl From<proc_macro2::TokenStream> for TokenStream {
-    fn from(value: proc_macro2::TokenStream) -> Self {
-        recursive_convert(value)
+
+/// This enum represents the category of a `Token`, without any of the data it carries.
+/// Use `Token::kind()` to get it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum TokenKind {
+    /// Represents `Token::Ident` and `Token::RawIdent`.
+    Ident,
+    /// Represents `Token::Underscore`.
+    Underscore,
+    /// Represents `Token::Lifetime`.
+    Lifetime,
+    /// Represents `Token::Bool`.
+    Bool,
+    /// Represents `Token::Integer`, `Token::BigInteger`, `Token::IntegerSuffixed` and `Token::BigIntegerSuffixed`.
+    Integer,
+    /// Represents `Token::Float`, `Token::FloatSuffixed` and `Token::FloatLit`.
+    Float,
+    /// Represents `Token::Char`.
+    Char,
+    /// Represents `Token::ByteChar`.
+    ByteChar,
+    /// Represents `Token::String` and `Token::RawString`.
+    String,
+    /// Represents `Token::ByteString` and `Token::RawByteString`.
+    ByteString,
+    /// Represents any punctuation, including multi-character operators.
+    Punct,
+    /// Represents any opening or closing delimiter, including invisible ones.
+    Delimiter,
+    /// Represents `Token::None`.
+    None,
+    /// Represents `Token::Literal`.
+    Literal,
+}
+
+impl std::fmt::Display for TokenKind {
+    /// This function renders a category the way error messages refer to it.
+    /// ```
+    /// use token_stream2::TokenKind;
+    ///
+    /// assert!(TokenKind::Ident.to_string() == "identifier");
+    /// assert!(TokenKind::ByteString.to_string() == "byte string");
+    /// ```
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            TokenKind::Ident => "identifier",
+            TokenKind::Underscore => "`_`",
+            TokenKind::Lifetime => "lifetime",
+            TokenKind::Bool => "boolean",
+            TokenKind::Integer => "integer",
+            TokenKind::Float => "float",
+            TokenKind::Char => "character",
+            TokenKind::ByteChar => "byte character",
+            TokenKind::String => "string",
+            TokenKind::ByteString => "byte string",
+            TokenKind::Punct => "punctuation",
+            TokenKind::Delimiter => "delimiter",
+            TokenKind::None => "nothing",
+            TokenKind::Literal => "literal",
+        })
     }
 }
 
-// This function recursively transforms a `proc_macro2::TokenStream` into a `token_stream2::TokenStream`.
-fn recursive_convert(tokens: proc_macro2::TokenStream) -> TokenStream {
-    let mut tokens_output = vec![];
-    let tokens = tokens.into_iter();
+/// This enum represents a coarse category of a `Token`, for code that wants to keep working as new tokens are added.
+/// Every token falls into exactly one category, and new tokens are sorted into the existing categories.
+/// Use `Token::as_category()` to get it, or `Token::kind()` for a finer classification.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Category {
+    /// Represents identifiers, including raw identifiers, keywords and `Token::Underscore`.
+    Ident,
+    /// Represents `Token::Lifetime`.
+    Lifetime,
+    /// Represents every literal, including `Token::Bool`. See `Token::is_literal()`.
+    Literal,
+    /// Represents any punctuation, including multi-character operators. See `Token::is_punct()`.
+    Punct,
+    /// Represents any opening or closing delimiter, including invisible ones.
+    Delimiter,
+    /// Represents `Token::None`.
+    None,
+}
 
-    for token in tokens {
-        if let proc_macro2::TokenTree::Group(group) = token {
-            tokens_output.push(SpannedToken {
-                token: match group.delimiter() {
-                    proc_macro2::Delimiter::Parenthesis => Token::OpenParen,
-                    proc_macro2::Delimiter::Brace => Token::OpenBrace,
-                    proc_macro2::Delimiter::Bracket => Token::OpenBracket,
-                    proc_macro2::Delimiter::None => Token::None,
-                },
-                span: group.span(),
-            });
-            tokens_output.extend(recursive_convert(group.stream()).tokens);
-            tokens_output.push(SpannedToken {
-                token: match group.delimiter() {
-                    proc_macro2::Delimiter::Parenthesis => Token::CloseParen,
-                    proc_macro2::Delimiter::Brace => Token::CloseBrace,
-                    proc_macro2::Delimiter::Bracket => Token::CloseBracket,
-                    proc_macro2::Delimiter::None => Token::None,
-                },
-                span: group.span(),
-            });
-        } else {
-            match token {
-                proc_macro2::TokenTree::Group(..) => unreachable!(),
-                proc_macro2::TokenTree::Ident(ident) => {
-                    tokens_output.push(SpannedToken {
-                        token: Token::Ident(ident.to_string()),
-                        span: ident.span(),
-                    });
-                }
-                proc_macro2::TokenTree::Punct(punct) => {
-                    let tok = match punct.as_char() {
-                        '+' => Token::Plus,
-                        '-' => Token::Minus,
-                        '>' => Token::GreaterThan,
-                        '<' => Token::LessThan,
-                        '@' => Token::At,
-                        '/' => Token::Slash,
-                        '*' => Token::Star,
-                        '&' => Token::Ampersand,
-                        ';' => Token::Semi,
-                        ':' => Token::Colon,
-                        '"' => Token::DoubleQuote,
-                        '\'' => Token::SingleQuote,
-                        '?' => Token::Question,
-                        '!' => Token::Bang,
-                        ',' => Token::Comma,
-                        '.' => Token::Dot,
-                        '~' => Token::Tilde,
-                        '%' => Token::Percent,
-                        '^' => Token::Caret,
-                        '|' => Token::Pipe,
-                        '#' => Token::Hash,
-                        '$' => Token::Dollar,
-                        '=' => Token::Equal,
-                        _ => unreachable!(),
-                    };
-                    tokens_output.push(SpannedToken {
-                        token: tok,
-                        span: punct.span(),
-                    });
-                }
-                proc_macro2::TokenTree::Literal(literal) => {
-                    let mut panic = true;
-                    if let Ok(int_value) = literal.to_string().parse::<i128>() {
-                        panic = false;
-                        tokens_output.push(SpannedToken {
-                            token: Token::Integer(int_value),
-                            span: literal.span(),
-                        });
-                    }
+/// This enum represents the value of any literal token, so code that handles every literal the same way only has to match one type.
+/// Use `Token::as_literal()` to get it.
+#[derive(Clone, Debug, PartialEq)]
+pub enum LiteralValue {
+    /// Represents `Token::Integer` and `Token::IntegerSuffixed`. The suffix is dropped.
+    Int(i128),
+    /// Represents `Token::Float`, `Token::FloatSuffixed` and `Token::FloatLit`. The suffix is dropped.
+    Float(f64),
+    /// Represents `Token::Char`.
+    Char(char),
+    /// Represents `Token::ByteChar`.
+    ByteChar(u8),
+    /// Represents `Token::String` and `Token::RawString`, with escape sequences decoded.
+    Str(String),
+    /// Represents `Token::ByteString` and `Token::RawByteString`, with escape sequences decoded.
+    ByteStr(Vec<u8>),
+    /// Represents any other literal, such as `Token::BigInteger`, `Token::BigIntegerSuffixed`, `Token::Bool` and `Token::Literal`, spelled as in source code.
+    Other(String),
+}
 
-                    if let Ok(float_value) = literal.to_string().parse::<f64>() {
-                        panic = false;
-                        tokens_output.push(SpannedToken {
-                            token: Token::Float(float_value),
-                            span: literal.span(),
-                        });
-                    }
-                    let str_value = literal.to_string();
-                    if str_value.starts_with("0x") {
-                        if let Ok(int_value) =
-                            i128::from_str_radix(str_value.trim_start_matches("0x"), 16)
-                        {
-                            panic = false;
-                            tokens_output.push(SpannedToken {
-                                token: Token::Integer(int_value),
-                                span: literal.span(),
-                            });
-                        }
-                    }
-                    if str_value.starts_with("b'") && str_value.ends_with('\'') {
-                        panic = false;
-                        let as_char = str_value
-                            .trim_start_matches("b\'")
-                            .trim_end_matches('\'')
-                            .parse::<char>()
-                            .expect("infallible - guaranteed to be a char");
-                        tokens_output.push(SpannedToken {
-                            token: Token::ByteChar(as_char),
-                            span: literal.span(),
-                        });
-                    }
-                    if str_value.starts_with('\'') && str_value.ends_with('\'') {
-                        panic = false;
-                        let as_char = str_value
-                            .trim_matches('\'')
-                            .trim()
-                            .parse::<char>()
-                            .expect("infallible - guaranteed to be a char");
-                        tokens_output.push(SpannedToken {
-                            token: Token::Char(as_char),
-                            span: literal.span(),
-                        });
-                    }
-                    if str_value.starts_with('"') && str_value.ends_with('"') {
-                        panic = false;
-                        tokens_output.push(SpannedToken {
-                            token: Token::String(str_value.trim_matches('"').to_string()),
-                            span: literal.span(),
-                        });
-                    }
-                    if str_value.starts_with("b\"") && str_value.ends_with('"') {
-                        panic = false;
-                        tokens_output.push(SpannedToken {
-                            token: Token::String(
-                                str_value
-                                    .trim_end_matches('"')
-                                    .trim_start_matches("b\"")
-                                    .to_string(),
-                            ),
-                            span: literal.span(),
-                        });
-                    }
-                    if panic {
-                        tokens_output.push(SpannedToken {
-                            token: Token::Literal(str_value),
-                            span: literal.span(),
-                        });
-                    }
-                }
+/// This struct is a set of token categories, used to report every category a parser would have accepted.
+/// It renders as a comma-separated list, in the order the categories are declared in `TokenKind`, so messages don't change between runs.
+/// ```
+/// use token_stream2::{ExpectedSet, TokenKind};
+///
+/// let set: ExpectedSet = vec![TokenKind::String, TokenKind::Ident, TokenKind::String].into_iter().collect();
+/// assert!(set.len() == 2);
+/// assert!(set.contains(TokenKind::Ident));
+/// assert!(set.to_string() == "identifier, string");
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ExpectedSet {
+    kinds: std::collections::HashSet<TokenKind>,
+}
+
+impl ExpectedSet {
+    /// This function creates an empty `ExpectedSet`.
+    pub fn new() -> Self {
+        ExpectedSet::default()
+    }
+    /// This function adds a category to the set, returning whether it wasn't already there.
+    pub fn insert(&mut self, kind: TokenKind) -> bool {
+        self.kinds.insert(kind)
+    }
+    /// This function returns whether the set contains a category.
+    pub fn contains(&self, kind: TokenKind) -> bool {
+        self.kinds.contains(&kind)
+    }
+    /// This function returns the number of categories in the set.
+    pub fn len(&self) -> usize {
+        self.kinds.len()
+    }
+    /// This function returns whether the set has no categories.
+    pub fn is_empty(&self) -> bool {
+        self.kinds.is_empty()
+    }
+    /// This function returns the categories in the set, in the order they are declared in `TokenKind`.
+    pub fn kinds(&self) -> Vec<TokenKind> {
+        let mut kinds: Vec<TokenKind> = self.kinds.iter().copied().collect();
+        kinds.sort_by_key(|kind| *kind as u8);
+        kinds
+    }
+}
+
+impl std::iter::FromIterator<TokenKind> for ExpectedSet {
+    fn from_iter<I: IntoIterator<Item = TokenKind>>(iter: I) -> Self {
+        ExpectedSet {
+            kinds: iter.into_iter().collect(),
+        }
+    }
+}
+
+impl std::fmt::Display for ExpectedSet {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (i, kind) in self.kinds().into_iter().enumerate() {
+            if i > 0 {
+                f.write_str(", ")?;
             }
+            write!(f, "{}", kind)?;
         }
+        Ok(())
+    }
+}
+
+impl Token {
+    /// This function returns the category of a token.
+    /// ```
+    /// use token_stream2::{Token, TokenKind};
+    ///
+    /// assert!(Token::Ident("foo".into()).kind() == TokenKind::Ident);
+    /// assert!(Token::IntegerSuffixed { value: 1, suffix: "u8".into() }.kind() == TokenKind::Integer);
+    /// assert!(Token::String("hi".into()).kind() == TokenKind::String);
+    /// assert!(Token::PathSep.kind() == TokenKind::Punct);
+    /// assert!(Token::CloseBrace.kind() == TokenKind::Delimiter);
+    /// ```
+    pub fn kind(&self) -> TokenKind {
+        match self {
+            Token::Ident(..) | Token::RawIdent(..) => TokenKind::Ident,
+            Token::Lifetime(..) => TokenKind::Lifetime,
+            Token::Bool(..) => TokenKind::Bool,
+            Token::Underscore => TokenKind::Underscore,
+            Token::Integer(..)
+            | Token::BigInteger(..)
+            | Token::IntegerSuffixed { .. }
+            | Token::BigIntegerSuffixed { .. } => TokenKind::Integer,
+            Token::Float(..) | Token::FloatSuffixed { .. } | Token::FloatLit { .. } => {
+                TokenKind::Float
+            }
+            Token::Char(..) => TokenKind::Char,
+            Token::ByteChar(..) => TokenKind::ByteChar,
+            Token::String(..) | Token::RawString { .. } => TokenKind::String,
+            Token::ByteString(..) | Token::RawByteString { .. } => TokenKind::ByteString,
+            Token::Plus
+            | Token::Minus
+            | Token::Slash
+            | Token::Star
+            | Token::At
+            | Token::Ampersand
+            | Token::Semi
+            | Token::Colon
+            | Token::GreaterThan
+            | Token::LessThan
+            | Token::Comma
+            | Token::SingleQuote
+            | Token::DoubleQuote
+            | Token::Bang
+            | Token::Question
+            | Token::Dot
+            | Token::Tilde
+            | Token::Percent
+            | Token::Caret
+            | Token::Pipe
+            | Token::Hash
+            | Token::Dollar
+            | Token::Equal
+            | Token::PathSep
+            | Token::RArrow
+            | Token::FatArrow
+            | Token::EqEq
+            | Token::Ne
+            | Token::Le
+            | Token::Ge
+            | Token::AndAnd
+            | Token::OrOr
+            | Token::Shl
+            | Token::Shr
+            | Token::UnknownPunct(..) => TokenKind::Punct,
+            Token::OpenBrace
+            | Token::CloseBrace
+            | Token::OpenBracket
+            | Token::CloseBracket
+            | Token::OpenParen
+            | Token::CloseParen
+            | Token::OpenNone
+            | Token::CloseNone => TokenKind::Delimiter,
+            Token::None => TokenKind::None,
+            Token::Literal(..) => TokenKind::Literal,
+        }
+    }
+
+    /// This function returns the coarse category of a token.
+    /// Unlike `Token`, `Category` doesn't grow as new tokens are added, so matching on it doesn't need a wildcard arm.
+    /// ```
+    /// use token_stream2::{Category, Token};
+    ///
+    /// let stream: token_stream2::TokenStream = r##"
+    ///     a r#b _ 'c 1 170141183460469231731687303715884105728 1.5 1u8 1.5f32 b'd' 'e' b"f" r"g" "h"
+    ///     + - / * @ & ; : > < , ! ? . ~ % ^ | # $ = :: -> => == != <= >= && || << >>
+    ///     { } [ ] ( )
+    /// "##
+    /// .parse()
+    /// .unwrap();
+    /// let categories: Vec<Category> = stream.map(|t| t.token().as_category()).collect();
+    /// assert!(categories[..3] == [Category::Ident; 3]);
+    /// assert!(categories[3] == Category::Lifetime);
+    /// assert!(categories[4..14] == [Category::Literal; 10]);
+    /// assert!(categories[14..46] == [Category::Punct; 32]);
+    /// assert!(categories[46..] == [Category::Delimiter; 6]);
+    ///
+    /// // These tokens aren't produced from plain source text, so they are checked directly.
+    /// assert!(Token::Bool(true).as_category() == Category::Literal);
+    /// assert!(Token::Underscore.as_category() == Category::Ident);
+    /// assert!(Token::FloatLit { value: 1.5, repr: "1.5".into() }.as_category() == Category::Literal);
+    /// assert!(Token::Literal("1e".into()).as_category() == Category::Literal);
+    /// assert!(Token::SingleQuote.as_category() == Category::Punct);
+    /// assert!(Token::DoubleQuote.as_category() == Category::Punct);
+    /// assert!(Token::UnknownPunct('\\').as_category() == Category::Punct);
+    /// assert!(Token::OpenNone.as_category() == Category::Delimiter);
+    /// assert!(Token::CloseNone.as_category() == Category::Delimiter);
+    /// assert!(Token::None.as_category() == Category::None);
+    /// ```
+    pub fn as_category(&self) -> Category {
+        match self.kind() {
+            TokenKind::Ident | TokenKind::Underscore => Category::Ident,
+            TokenKind::Lifetime => Category::Lifetime,
+            TokenKind::Bool
+            | TokenKind::Integer
+            | TokenKind::Float
+            | TokenKind::Char
+            | TokenKind::ByteChar
+            | TokenKind::String
+            | TokenKind::ByteString
+            | TokenKind::Literal => Category::Literal,
+            TokenKind::Punct => Category::Punct,
+            TokenKind::Delimiter => Category::Delimiter,
+            TokenKind::None => Category::None,
+        }
+    }
+
+    /// This function returns whether the token is an identifier, including raw identifiers and keywords.
+    /// ```
+    /// use token_stream2::Token;
+    ///
+    /// assert!(Token::Ident("foo".into()).is_ident());
+    /// assert!(Token::RawIdent("type".into()).is_ident());
+    /// assert!(!Token::String("foo".into()).is_ident());
+    /// ```
+    pub fn is_ident(&self) -> bool {
+        self.kind() == TokenKind::Ident
+    }
+
+    /// This function returns whether the token is any kind of literal.
+    /// ```
+    /// use token_stream2::Token;
+    ///
+    /// assert!(Token::Integer(1).is_literal());
+    /// assert!(Token::Float(1.5).is_literal());
+    /// assert!(Token::Char('a').is_literal());
+    /// assert!(Token::ByteString("a".into()).is_literal());
+    /// assert!(Token::Literal("1e".into()).is_literal());
+    /// assert!(!Token::Ident("a".into()).is_literal());
+    /// ```
+    pub fn is_literal(&self) -> bool {
+        matches!(
+            self.kind(),
+            TokenKind::Bool
+                | TokenKind::Integer
+                | TokenKind::Float
+                | TokenKind::Char
+                | TokenKind::ByteChar
+                | TokenKind::String
+                | TokenKind::ByteString
+                | TokenKind::Literal
+        )
+    }
+
+    /// This function returns whether the token is punctuation. This includes multi-character operators like `::`, but not delimiters.
+    /// ```
+    /// use token_stream2::Token;
+    ///
+    /// assert!(Token::Plus.is_punct());
+    /// assert!(Token::PathSep.is_punct());
+    /// assert!(!Token::OpenParen.is_punct());
+    /// ```
+    pub fn is_punct(&self) -> bool {
+        self.kind() == TokenKind::Punct
+    }
+
+    /// This function returns whether the token is an arithmetic operator: `+`, `-`, `*`, `/` or `%`.
+    /// ```
+    /// use token_stream2::Token;
+    ///
+    /// assert!(Token::Plus.is_arithmetic_op());
+    /// assert!(Token::Percent.is_arithmetic_op());
+    /// assert!(!Token::Caret.is_arithmetic_op());
+    /// ```
+    pub fn is_arithmetic_op(&self) -> bool {
+        matches!(
+            self,
+            Token::Plus | Token::Minus | Token::Star | Token::Slash | Token::Percent
+        )
+    }
+
+    /// This function returns whether the token is a comparison operator: `==`, `!=`, `<`, `<=`, `>` or `>=`.
+    /// ```
+    /// use token_stream2::Token;
+    ///
+    /// assert!(Token::EqEq.is_comparison_op());
+    /// assert!(Token::LessThan.is_comparison_op());
+    /// assert!(Token::Ge.is_comparison_op());
+    /// assert!(!Token::Equal.is_comparison_op());
+    /// ```
+    pub fn is_comparison_op(&self) -> bool {
+        matches!(
+            self,
+            Token::EqEq | Token::Ne | Token::LessThan | Token::Le | Token::GreaterThan | Token::Ge
+        )
     }
 
-    TokenStream {
-        tokens: tokens_output,
-        iter_ptr: 0,
+    /// This function returns whether the token is a logical operator: `&&`, `||` or `!`.
+    /// ```
+    /// use token_stream2::Token;
+    ///
+    /// assert!(Token::AndAnd.is_logical_op());
+    /// assert!(Token::Bang.is_logical_op());
+    /// assert!(!Token::Ampersand.is_logical_op());
+    /// ```
+    pub fn is_logical_op(&self) -> bool {
+        matches!(self, Token::AndAnd | Token::OrOr | Token::Bang)
+    }
+
+    /// This function returns whether the token is a bitwise operator: `&`, `|`, `^`, `~`, `<<` or `>>`.
+    /// ```
+    /// use token_stream2::Token;
+    ///
+    /// assert!(Token::Pipe.is_bitwise_op());
+    /// assert!(Token::Shl.is_bitwise_op());
+    /// assert!(!Token::OrOr.is_bitwise_op());
+    /// ```
+    pub fn is_bitwise_op(&self) -> bool {
+        matches!(
+            self,
+            Token::Ampersand | Token::Pipe | Token::Caret | Token::Tilde | Token::Shl | Token::Shr
+        )
+    }
+
+    /// This function returns whether the token is the assignment operator `=`.
+    /// Compound assignments like `+=` are made of two tokens, so they aren't included.
+    /// ```
+    /// use token_stream2::Token;
+    ///
+    /// assert!(Token::Equal.is_assignment_op());
+    /// assert!(!Token::EqEq.is_assignment_op());
+    /// ```
+    pub fn is_assignment_op(&self) -> bool {
+        matches!(self, Token::Equal)
+    }
+
+    /// This function returns whether the token opens a group.
+    /// ```
+    /// use token_stream2::Token;
+    ///
+    /// assert!(Token::OpenParen.is_open_delim());
+    /// assert!(Token::OpenNone.is_open_delim());
+    /// assert!(!Token::CloseParen.is_open_delim());
+    /// ```
+    pub fn is_open_delim(&self) -> bool {
+        matches!(
+            self,
+            Token::OpenParen | Token::OpenBrace | Token::OpenBracket | Token::OpenNone
+        )
+    }
+
+    /// This function returns whether the token closes a group.
+    /// ```
+    /// use token_stream2::Token;
+    ///
+    /// assert!(Token::CloseBrace.is_close_delim());
+    /// assert!(Token::CloseNone.is_close_delim());
+    /// assert!(!Token::OpenBrace.is_close_delim());
+    /// ```
+    pub fn is_close_delim(&self) -> bool {
+        matches!(
+            self,
+            Token::CloseParen | Token::CloseBrace | Token::CloseBracket | Token::CloseNone
+        )
+    }
+
+    /// This function returns the kind of group that the token opens or closes, if it is a delimiter.
+    /// Each kind of delimiter has its own pair of tokens, so this is how invisible `Delimiter::None` groups can be told apart.
+    /// ```
+    /// use proc_macro2::Delimiter;
+    /// use token_stream2::Token;
+    ///
+    /// assert!(Token::OpenBracket.delimiter() == Some(Delimiter::Bracket));
+    /// assert!(Token::CloseNone.delimiter() == Some(Delimiter::None));
+    /// assert!(Token::Ident("a".into()).delimiter() == None);
+    /// ```
+    pub fn delimiter(&self) -> Option<proc_macro2::Delimiter> {
+        open_delimiter(self).or_else(|| close_delimiter(self))
+    }
+
+    /// This function returns whether the token is an identifier that is a reserved Rust keyword.
+    /// Raw identifiers are never keywords.
+    /// ```
+    /// use token_stream2::Token;
+    ///
+    /// assert!(Token::Ident("fn".into()).is_keyword());
+    /// assert!(!Token::Ident("foo".into()).is_keyword());
+    /// assert!(!Token::RawIdent("fn".into()).is_keyword());
+    /// ```
+    pub fn is_keyword(&self) -> bool {
+        self.as_keyword().is_some()
+    }
+
+    /// This function classifies an identifier as a keyword. Raw identifiers and other tokens return `None`.
+    /// ```
+    /// use token_stream2::{Keyword, Token};
+    ///
+    /// assert!(Token::Ident("fn".into()).as_keyword() == Some(Keyword::Fn));
+    /// assert!(Token::Ident("foo".into()).as_keyword() == None);
+    /// assert!(Token::RawIdent("fn".into()).as_keyword() == None);
+    /// ```
+    pub fn as_keyword(&self) -> Option<Keyword> {
+        match self {
+            Token::Ident(ident) => KEYWORDS
+                .iter()
+                .find(|(name, _)| name == ident)
+                .map(|(_, keyword)| *keyword),
+            _ => None,
+        }
+    }
+
+    /// This function returns the value of a string literal with its escape sequences decoded.
+    /// Raw strings are returned verbatim, and any other token returns `None`.
+    /// ```
+    /// let to_parse: proc_macro2::TokenStream = r#""a\n\t\\\"\x41\u{1F600}""#.parse().expect("infallible");
+    ///
+    /// let mut stream: token_stream2::TokenStream = to_parse.into();
+    /// let token = stream.next().unwrap().token().clone();
+    /// assert!(token.as_unescaped_string() == Some("a\n\t\\\"A\u{1F600}".to_string()));
+    /// assert!(token_stream2::Token::Comma.as_unescaped_string() == None);
+    /// ```
+    pub fn as_unescaped_string(&self) -> Option<String> {
+        match self {
+            Token::String(content) => unescape_string(content),
+            Token::RawString { content, .. } => Some(content.clone()),
+            _ => None,
+        }
+    }
+
+    /// This function returns the character of a single-character punctuation token.
+    /// Multi-character operators like `Token::PathSep`, and every other token, return `None`.
+    /// ```
+    /// use token_stream2::Token;
+    ///
+    /// let puncts = "+ - > < @ / * & ; : ? ! , . ~ % ^ | # $ =";
+    /// let stream: token_stream2::TokenStream = puncts.parse().unwrap();
+    /// let chars: Vec<char> = stream.map(|t| t.token().punct_char().unwrap()).collect();
+    /// assert!(chars.iter().collect::<String>() == puncts.replace(' ', ""));
+    ///
+    /// // A lone quote can't be parsed from source text, so these are checked directly.
+    /// assert!(Token::SingleQuote.punct_char() == Some('\''));
+    /// assert!(Token::DoubleQuote.punct_char() == Some('"'));
+    /// assert!(Token::PathSep.punct_char() == None);
+    /// assert!(Token::OpenParen.punct_char() == None);
+    /// ```
+    pub fn punct_char(&self) -> Option<char> {
+        PUNCTS
+            .iter()
+            .find(|(_, tok)| tok == self)
+            .map(|(ch, _)| *ch)
+    }
+
+    /// This function returns the value of an integer, with or without a suffix.
+    /// ```
+    /// use token_stream2::Token;
+    ///
+    /// assert!(Token::Integer(42).as_integer() == Some(42));
+    /// assert!(Token::IntegerSuffixed { value: 7, suffix: "u8".into() }.as_integer() == Some(7));
+    /// assert!(Token::BigInteger("170141183460469231731687303715884105728".into()).as_integer() == None);
+    /// assert!(Token::Float(1.0).as_integer() == None);
+    /// ```
+    pub fn as_integer(&self) -> Option<i128> {
+        match self {
+            Token::Integer(value) | Token::IntegerSuffixed { value, .. } => Some(*value),
+            _ => None,
+        }
+    }
+
+    /// This function returns the value of a float, with or without a suffix.
+    /// ```
+    /// use token_stream2::Token;
+    ///
+    /// assert!(Token::Float(2.5).as_float() == Some(2.5));
+    /// assert!(Token::FloatSuffixed { value: 0.5, suffix: "f32".into() }.as_float() == Some(0.5));
+    /// assert!(Token::Integer(1).as_float() == None);
+    /// ```
+    pub fn as_float(&self) -> Option<f64> {
+        match self {
+            Token::Float(value)
+            | Token::FloatSuffixed { value, .. }
+            | Token::FloatLit { value, .. } => Some(*value),
+            _ => None,
+        }
+    }
+
+    /// This function returns the name of an identifier. Raw identifiers are returned without their `r#` prefix.
+    /// ```
+    /// use token_stream2::Token;
+    ///
+    /// assert!(Token::Ident("foo".into()).as_ident() == Some("foo"));
+    /// assert!(Token::RawIdent("type".into()).as_ident() == Some("type"));
+    /// assert!(Token::Lifetime("a".into()).as_ident() == None);
+    /// ```
+    pub fn as_ident(&self) -> Option<&str> {
+        match self {
+            Token::Ident(name) | Token::RawIdent(name) => Some(name),
+            _ => None,
+        }
+    }
+
+    /// This function returns the contents of a string literal as they were written, without decoding escape sequences.
+    /// Use `Token::as_unescaped_string()` to decode them.
+    /// ```
+    /// use token_stream2::Token;
+    ///
+    /// assert!(Token::String("a\\n".into()).as_string() == Some("a\\n"));
+    /// assert!(Token::RawString { content: "b".into(), hashes: 1 }.as_string() == Some("b"));
+    /// assert!(Token::ByteString("c".into()).as_string() == None);
+    /// assert!(Token::Ident("d".into()).as_string() == None);
+    /// ```
+    pub fn as_string(&self) -> Option<&str> {
+        match self {
+            Token::String(content) | Token::RawString { content, .. } => Some(content),
+            _ => None,
+        }
+    }
+
+    /// This function returns the value of a character literal. Byte characters return `None`.
+    /// ```
+    /// use token_stream2::Token;
+    ///
+    /// assert!(Token::Char('x').as_char() == Some('x'));
+    /// assert!(Token::ByteChar(b'x').as_char() == None);
+    /// assert!(Token::Integer(120).as_char() == None);
+    /// ```
+    pub fn as_char(&self) -> Option<char> {
+        match self {
+            Token::Char(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    /// This function returns the value of a byte character literal. Characters return `None`.
+    /// ```
+    /// use token_stream2::Token;
+    ///
+    /// let to_parse: proc_macro2::TokenStream = r"b'a' b'\n' b'\xff' 'a'".parse().expect("infallible");
+    ///
+    /// let stream: token_stream2::TokenStream = to_parse.into();
+    /// let bytes: Vec<Option<u8>> = stream.map(|t| t.token().as_byte()).collect();
+    /// assert!(bytes == vec![Some(b'a'), Some(b'\n'), Some(0xff), None]);
+    /// ```
+    pub fn as_byte(&self) -> Option<u8> {
+        match self {
+            Token::ByteChar(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    /// This function returns the value of any literal token as a `LiteralValue`. Tokens that aren't literals return `None`.
+    /// ```
+    /// use token_stream2::{LiteralValue, Token};
+    ///
+    /// let literal = |source: &str| -> Option<LiteralValue> {
+    ///     let mut stream: token_stream2::TokenStream = source.parse().unwrap();
+    ///     stream.next().unwrap().token().as_literal()
+    /// };
+    /// assert!(literal("42") == Some(LiteralValue::Int(42)));
+    /// assert!(literal("7u8") == Some(LiteralValue::Int(7)));
+    /// assert!(literal("2.5") == Some(LiteralValue::Float(2.5)));
+    /// assert!(literal("0.5f32") == Some(LiteralValue::Float(0.5)));
+    /// assert!(literal("'x'") == Some(LiteralValue::Char('x')));
+    /// assert!(literal("b'x'") == Some(LiteralValue::ByteChar(b'x')));
+    /// assert!(literal(r#""a\n""#) == Some(LiteralValue::Str("a\n".into())));
+    /// assert!(literal(r##"r#"a\n"#"##) == Some(LiteralValue::Str(r"a\n".into())));
+    /// assert!(literal(r#"b"a\n\xff""#) == Some(LiteralValue::ByteStr(b"a\n\xff".to_vec())));
+    /// assert!(literal(r#"br"a\n""#) == Some(LiteralValue::ByteStr(br"a\n".to_vec())));
+    /// assert!(literal("170141183460469231731687303715884105728")
+    ///     == Some(LiteralValue::Other("170141183460469231731687303715884105728".into())));
+    /// assert!(Token::Bool(true).as_literal() == Some(LiteralValue::Other("true".into())));
+    /// assert!(Token::Literal("1e".into()).as_literal() == Some(LiteralValue::Other("1e".into())));
+    /// assert!(literal("x") == None);
+    /// ```
+    pub fn as_literal(&self) -> Option<LiteralValue> {
+        Some(match self {
+            Token::Integer(value) | Token::IntegerSuffixed { value, .. } => {
+                LiteralValue::Int(*value)
+            }
+            Token::Float(value)
+            | Token::FloatSuffixed { value, .. }
+            | Token::FloatLit { value, .. } => LiteralValue::Float(*value),
+            Token::Char(value) => LiteralValue::Char(*value),
+            Token::ByteChar(value) => LiteralValue::ByteChar(*value),
+            Token::String(..) | Token::RawString { .. } => match self.as_unescaped_string() {
+                Some(value) => LiteralValue::Str(value),
+                None => LiteralValue::Other(self.to_string()),
+            },
+            Token::ByteString(content) => match unescape_byte_string(content) {
+                Some(value) => LiteralValue::ByteStr(value),
+                None => LiteralValue::Other(self.to_string()),
+            },
+            Token::RawByteString { content, .. } => {
+                LiteralValue::ByteStr(content.as_bytes().to_vec())
+            }
+            Token::BigInteger(..)
+            | Token::BigIntegerSuffixed { .. }
+            | Token::Bool(..)
+            | Token::Literal(..) => LiteralValue::Other(self.to_string()),
+            _ => return None,
+        })
+    }
+}
+
+/// This enum represents the reasons a conversion between a `proc_macro2::TokenStream` and a `token_stream2::TokenStream` can fail, in either direction.
+/// Use `ConvertError::span()` to find where the offending token is.
+#[derive(Clone, Debug)]
+pub enum ConvertError {
+    /// Represents source text that could not be tokenized. This is only produced when parsing a `TokenStream` from a string.
+    LexError {
+        message: String,
+        span: proc_macro2::Span,
+    },
+    /// Represents a literal that looks like a known kind of literal, but could not be decoded.
+    /// `proc_macro2` rejects malformed literals while lexing, and every literal it accepts today can be decoded,
+    /// so converting a `proc_macro2::TokenStream` doesn't currently produce this. It is kept so a literal this crate can't decode
+    /// is reported instead of being silently misread.
+    InvalidLiteral {
+        literal: String,
+        span: proc_macro2::Span,
+    },
+    /// Represents a closing delimiter at `index` that doesn't close the innermost open group.
+    /// `expected` is the closing delimiter that group needs, or `None` if no group is open.
+    UnexpectedDelimiter {
+        index: usize,
+        found: Token,
+        expected: Option<Token>,
+        span: proc_macro2::Span,
+    },
+    /// Represents an opening delimiter at `index` that is never closed. `expected` is the closing delimiter it needs.
+    UnclosedDelimiter {
+        index: usize,
+        open: Token,
+        expected: Token,
+        span: proc_macro2::Span,
+    },
+    /// Represents a token at `index` that has no `proc_macro2` equivalent, such as an identifier that isn't valid.
+    InvalidToken {
+        index: usize,
+        token: Token,
+        span: proc_macro2::Span,
+    },
+}
+
+impl ConvertError {
+    /// This function allows you to get the span of the token that failed to convert.
+    pub fn span(&self) -> &proc_macro2::Span {
+        match self {
+            ConvertError::LexError { span, .. } => span,
+            ConvertError::InvalidLiteral { span, .. } => span,
+            ConvertError::UnexpectedDelimiter { span, .. } => span,
+            ConvertError::UnclosedDelimiter { span, .. } => span,
+            ConvertError::InvalidToken { span, .. } => span,
+        }
+    }
+    /// This function turns the error into a `compile_error!` invocation at its span, see `ParseError::to_compile_error()`.
+    /// ```
+    /// let err = "(".parse::<token_stream2::TokenStream>().unwrap_err();
+    /// assert!(err.to_compile_error().to_string().starts_with(":: core :: compile_error !"));
+    /// ```
+    pub fn to_compile_error(&self) -> proc_macro2::TokenStream {
+        ParseError::new(*self.span(), self.to_string()).to_compile_error()
+    }
+}
+
+impl std::fmt::Display for ConvertError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ConvertError::LexError { message, .. } => f.write_str(message),
+            ConvertError::InvalidLiteral { literal, .. } => {
+                write!(f, "invalid literal `{}`", literal)
+            }
+            ConvertError::UnexpectedDelimiter {
+                index,
+                found,
+                expected: Some(expected),
+                ..
+            } => write!(
+                f,
+                "unexpected `{}` at index {}, expected `{}`",
+                found, index, expected
+            ),
+            ConvertError::UnexpectedDelimiter {
+                index,
+                found,
+                expected: None,
+                ..
+            } => write!(
+                f,
+                "unexpected `{}` at index {}, no group is open",
+                found, index
+            ),
+            ConvertError::UnclosedDelimiter {
+                index,
+                open,
+                expected,
+                ..
+            } => write!(
+                f,
+                "unclosed `{}` at index {}, expected `{}`",
+                open, index, expected
+            ),
+            ConvertError::InvalidToken { index, token, .. } => {
+                write!(
+                    f,
+                    "`{}` at index {} has no proc_macro2 equivalent",
+                    token, index
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for ConvertError {}
+
+/// This struct controls how a `proc_macro2::TokenStream` is converted into a `token_stream2::TokenStream`.
+/// Every option is disabled by default, which is what the `From` implementation uses.
+#[derive(Clone, Debug, Default)]
+pub struct ConvertOptions {
+    bool_literals: bool,
+    float_text: bool,
+    underscore: bool,
+    signed_numbers: bool,
+}
+
+impl ConvertOptions {
+    /// This function creates a `ConvertOptions` with every option disabled.
+    pub fn new() -> Self {
+        ConvertOptions::default()
+    }
+    /// This function controls whether the identifiers `true` and `false` are converted into `Token::Bool`.
+    /// Raw identifiers like `r#true` are always left as identifiers.
+    pub fn bool_literals(mut self, enabled: bool) -> Self {
+        self.bool_literals = enabled;
+        self
+    }
+    /// This function controls whether floats are converted into `Token::FloatLit`, which keeps the text they were written as,
+    /// instead of `Token::Float` and `Token::FloatSuffixed`.
+    pub fn float_text(mut self, enabled: bool) -> Self {
+        self.float_text = enabled;
+        self
+    }
+    /// This function controls whether the identifier `_` is converted into `Token::Underscore`.
+    pub fn underscore(mut self, enabled: bool) -> Self {
+        self.underscore = enabled;
+        self
+    }
+    /// This function controls whether signs in front of numbers are folded into them, see `TokenStream::fold_signed_numbers()`.
+    /// ```
+    /// use token_stream2::{ConvertOptions, LazyTokenStream, Token, TokenStream};
+    ///
+    /// let to_parse: proc_macro2::TokenStream = "f(-1, x * -2i8) - 3 + (-5) x? - 1".parse().expect("infallible");
+    ///
+    /// let options = ConvertOptions::new().signed_numbers(true);
+    /// let lazy: Vec<Token> = LazyTokenStream::with_options(to_parse.clone(), options)
+    ///     .map(|t| t.unwrap().token().clone())
+    ///     .collect();
+    /// let folded: Vec<Token> = TokenStream::from(to_parse).fold_signed_numbers().map(|t| t.token().clone()).collect();
+    /// assert!(lazy == folded);
+    /// assert!(lazy[2] == Token::Integer(-1));
+    /// assert!(lazy[8] == Token::Minus);
+    /// ```
+    pub fn signed_numbers(mut self, enabled: bool) -> Self {
+        self.signed_numbers = enabled;
+        self
+    }
+}
+
+/// This struct represents an error found while parsing a `TokenStream`.
+/// It carries the span the error should be reported at, along with the token that was expected and the token that was found, if known.
+#[derive(Clone, Debug)]
+pub struct ParseError {
+    span: proc_macro2::Span,
+    message: String,
+    expected: Option<Box<Token>>,
+    expected_kinds: Option<ExpectedSet>,
+    found: Option<Box<Token>>,
+}
+
+impl ParseError {
+    /// This function creates a `ParseError` with a message, to be reported at `span`.
+    pub fn new(span: proc_macro2::Span, message: impl Into<String>) -> Self {
+        ParseError {
+            span,
+            message: message.into(),
+            expected: None,
+            expected_kinds: None,
+            found: None,
+        }
+    }
+    /// This function attaches the token that was expected to a `ParseError`.
+    pub fn with_expected(mut self, expected: Token) -> Self {
+        self.expected = Some(Box::new(expected));
+        self
+    }
+    /// This function attaches the categories of token that were expected to a `ParseError`.
+    pub fn with_expected_kinds(mut self, kinds: ExpectedSet) -> Self {
+        self.expected_kinds = Some(kinds);
+        self
+    }
+    /// This function attaches the token that was found to a `ParseError`.
+    pub fn with_found(mut self, found: Token) -> Self {
+        self.found = Some(Box::new(found));
+        self
+    }
+    /// This function allows you to get the span of a `ParseError`.
+    pub fn span(&self) -> &proc_macro2::Span {
+        &self.span
+    }
+    /// This function allows you to get the message of a `ParseError`.
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+    /// This function allows you to get the token that was expected, if known.
+    pub fn expected(&self) -> Option<&Token> {
+        self.expected.as_deref()
+    }
+    /// This function allows you to get the categories of token that were expected, if known.
+    pub fn expected_kinds(&self) -> Option<&ExpectedSet> {
+        self.expected_kinds.as_ref()
+    }
+    /// This function allows you to get the token that was found, if known. This is `None` at the end of the stream.
+    pub fn found(&self) -> Option<&Token> {
+        self.found.as_deref()
+    }
+    /// This function turns a `ParseError` into a `compile_error!` invocation at its span.
+    /// A procedural macro can return this instead of panicking, so the message shows up as a normal compiler error.
+    /// ```
+    /// use token_stream2::{ParseError, Token, TokenStream};
+    ///
+    /// let error = ParseError::new(proc_macro2::Span::call_site(), "expected `;`");
+    /// let tokens: TokenStream = error.to_compile_error().into();
+    /// let tokens: Vec<Token> = tokens.map(|t| t.token().clone()).collect();
+    /// assert!(
+    ///     tokens
+    ///         == vec![
+    ///             Token::PathSep,
+    ///             Token::Ident("core".into()),
+    ///             Token::PathSep,
+    ///             Token::Ident("compile_error".into()),
+    ///             Token::Bang,
+    ///             Token::OpenBrace,
+    ///             Token::String("expected `;`".into()),
+    ///             Token::CloseBrace,
+    ///         ]
+    /// );
+    /// ```
+    pub fn to_compile_error(&self) -> proc_macro2::TokenStream {
+        let span = self.span;
+        let path_sep = || {
+            let mut first = proc_macro2::Punct::new(':', proc_macro2::Spacing::Joint);
+            let mut second = proc_macro2::Punct::new(':', proc_macro2::Spacing::Alone);
+            first.set_span(span);
+            second.set_span(span);
+            [proc_macro2::TokenTree::from(first), second.into()]
+        };
+        let mut bang = proc_macro2::Punct::new('!', proc_macro2::Spacing::Alone);
+        bang.set_span(span);
+        let mut message = proc_macro2::Literal::string(&self.message);
+        message.set_span(span);
+        let mut group = proc_macro2::Group::new(
+            proc_macro2::Delimiter::Brace,
+            proc_macro2::TokenTree::from(message).into(),
+        );
+        group.set_span(span);
+
+        let mut tokens = vec![];
+        tokens.extend(path_sep());
+        tokens.push(proc_macro2::Ident::new("core", span).into());
+        tokens.extend(path_sep());
+        tokens.push(proc_macro2::Ident::new("compile_error", span).into());
+        tokens.push(bang.into());
+        tokens.push(group.into());
+        tokens.into_iter().collect()
+    }
+}
+
+impl std::fmt::Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// This is an equivalent to the `Token` type with a span attached. Use `SpannedToken::span()` to retrieve it's span, and `SpannedToken::token()` to retreieve it's token.
+/// Note that this type is read-only, you ideally should not mutate it.
+#[derive(Clone, Debug)]
+pub struct SpannedToken {
+    token: Token,
+    span: proc_macro2::Span,
+    spacing: Option<proc_macro2::Spacing>,
+}
+
+impl SpannedToken {
+    /// This function creates a `SpannedToken` by hand, for building a `TokenStream` without `proc_macro2`.
+    /// The token has no spacing, see `SpannedToken::spacing()`.
+    /// ```
+    /// use token_stream2::{SpannedToken, Token};
+    ///
+    /// let token = SpannedToken::new(Token::Plus, proc_macro2::Span::call_site());
+    /// assert!(token.token() == &Token::Plus);
+    /// assert!(token.spacing() == None);
+    /// ```
+    pub fn new(token: Token, span: proc_macro2::Span) -> Self {
+        SpannedToken {
+            token,
+            span,
+            spacing: None,
+        }
+    }
+    /// This function allows you to get the internal `Token` of a `SpannedToken`.
+    /// ```
+    /// let to_parse: proc_macro2::TokenStream = r#"
+    ///      fn main() {
+    ///          println!("Hello world!");
+    ///      }
+    ///  "#
+    ///  .parse()
+    ///  .expect("infallible");
+    ///
+    /// let mut stream: token_stream2::TokenStream = to_parse.into();
+    /// assert!(stream.peek(2).unwrap().token() == &token_stream2::Token::OpenParen);
+    /// assert!(stream.peek(3).unwrap().token() == &token_stream2::Token::CloseParen);
+    /// ```
+    pub fn token(&self) -> &Token {
+        &self.token
+    }
+    /// This function allows you to get the span of a SpannedToken.
+    /// An example was failed to be provided for this function, sorry.
+    pub fn span(&self) -> &proc_macro2::Span {
+        &self.span
+    }
+    /// This function returns the source code that the token was converted from, exactly as it was written.
+    /// It returns `None` when the span doesn't point into real source code, such as for `Span::call_site()` or tokens created with `SpannedToken::new()`.
+    /// Outside of a procedural macro, the source is only known with `proc-macro2`'s `span-locations` feature enabled.
+    /// ```
+    /// let to_parse: proc_macro2::TokenStream = r#"print("hi\n")"#.parse().expect("infallible");
+    ///
+    /// let stream: token_stream2::TokenStream = to_parse.into();
+    /// assert!(stream[2].token() == &token_stream2::Token::String("hi\\n".into()));
+    /// assert!(stream[2].source_text() == Some(r#""hi\n""#.to_string()));
+    ///
+    /// let synthetic = token_stream2::SpannedToken::new(token_stream2::Token::Comma, proc_macro2::Span::call_site());
+    /// assert!(synthetic.source_text().is_none());
+    /// ```
+    pub fn source_text(&self) -> Option<String> {
+        self.span.source_text()
+    }
+    /// This function returns the line and column where the token starts and ends. Lines start at 1 and columns start at 0.
+    /// It returns `None` if the span has no location, which happens inside a procedural macro on compilers that don't expose it.
+    /// This function requires the `span-locations` feature.
+    /// ```
+    /// let to_parse: proc_macro2::TokenStream = "fn main() {\n    run();\n}".parse().expect("infallible");
+    ///
+    /// let stream: token_stream2::TokenStream = to_parse.into();
+    /// let (start, end) = stream[5].location().unwrap();
+    /// assert!(stream[5].token() == &token_stream2::Token::Ident("run".into()));
+    /// assert!(start.line == 2 && start.column == 4);
+    /// assert!(end.line == 2 && end.column == 7);
+    /// ```
+    #[cfg(feature = "span-locations")]
+    pub fn location(&self) -> Option<(proc_macro2::LineColumn, proc_macro2::LineColumn)> {
+        let start = self.span.start();
+        if start.line == 0 {
+            return None;
+        }
+        Some((start, self.span.end()))
+    }
+    /// This function returns the range of bytes the token covers in its source text.
+    /// It returns `None` if the span has no location, which happens inside a procedural macro on compilers that don't expose it.
+    /// Without the `span-locations` feature, it always returns `None`.
+    /// ```
+    /// let source = "let answer = 42;";
+    /// let to_parse: proc_macro2::TokenStream = source.parse().expect("infallible");
+    ///
+    /// let stream: token_stream2::TokenStream = to_parse.into();
+    /// let range = stream[1].byte_range().unwrap();
+    /// assert!(range == (4..10));
+    /// assert!(&source[range] == "answer");
+    /// ```
+    #[cfg(feature = "span-locations")]
+    pub fn byte_range(&self) -> Option<std::ops::Range<usize>> {
+        let range = self.span.byte_range();
+        if range.is_empty() {
+            return None;
+        }
+        Some(range)
+    }
+    /// This function returns the range of bytes the token covers in its source text.
+    /// Without the `span-locations` feature, which is disabled, it always returns `None`.
+    /// ```
+    /// let to_parse: proc_macro2::TokenStream = "let answer = 42;".parse().expect("infallible");
+    ///
+    /// let stream: token_stream2::TokenStream = to_parse.into();
+    /// assert!(stream[1].byte_range().is_none());
+    /// ```
+    #[cfg(not(feature = "span-locations"))]
+    pub fn byte_range(&self) -> Option<std::ops::Range<usize>> {
+        None
+    }
+    /// This function allows you to get the spacing of a punctuation token.
+    /// The spacing is `Joint` if the punctuation is immediately followed by more punctuation, and `Alone` otherwise.
+    /// Tokens that are not punctuation have no spacing.
+    /// ```
+    /// use proc_macro2::Spacing;
+    ///
+    /// let joint: proc_macro2::TokenStream = "a ++b".parse().expect("infallible");
+    /// let alone: proc_macro2::TokenStream = "a + +b".parse().expect("infallible");
+    ///
+    /// let mut joint: token_stream2::TokenStream = joint.into();
+    /// let mut alone: token_stream2::TokenStream = alone.into();
+    /// assert!(joint.peek(0).unwrap().spacing() == None);
+    /// assert!(joint.peek(1).unwrap().spacing() == Some(Spacing::Joint));
+    /// assert!(alone.peek(1).unwrap().spacing() == Some(Spacing::Alone));
+    /// ```
+    pub fn spacing(&self) -> Option<proc_macro2::Spacing> {
+        self.spacing
+    }
+    /// This function allows you to get the category of a SpannedToken's token. See `Token::kind()`.
+    pub fn kind(&self) -> TokenKind {
+        self.token.kind()
+    }
+    /// This function returns the kind of group that a delimiter opens or closes. See `Token::delimiter()`.
+    /// ```
+    /// use proc_macro2::{Delimiter, Group, TokenStream, TokenTree};
+    ///
+    /// let inner: TokenStream = "a + b".parse().expect("infallible");
+    /// let group = TokenTree::Group(Group::new(Delimiter::None, inner));
+    /// let stream: token_stream2::TokenStream = TokenStream::from(group).into();
+    /// assert!(stream[0].delimiter() == Some(Delimiter::None));
+    /// assert!(stream[1].delimiter() == None);
+    /// assert!(stream[4].delimiter() == Some(Delimiter::None));
+    /// assert!(stream.matching_delimiter(0) == Some(4));
+    /// ```
+    pub fn delimiter(&self) -> Option<proc_macro2::Delimiter> {
+        self.token.delimiter()
+    }
+}
+
+impl PartialEq for SpannedToken {
+    /// This function compares two spanned tokens by their tokens only. Spans and spacing are ignored.
+    /// ```
+    /// let first: proc_macro2::TokenStream = "a".parse().expect("infallible");
+    /// let second: proc_macro2::TokenStream = "   a".parse().expect("infallible");
+    ///
+    /// let mut first: token_stream2::TokenStream = first.into();
+    /// let mut second: token_stream2::TokenStream = second.into();
+    /// let first = first.next().unwrap();
+    /// let second = second.next().unwrap();
+    /// assert!(first.span().start().column != second.span().start().column);
+    /// assert!(first == second);
+    /// ```
+    fn eq(&self, other: &Self) -> bool {
+        self.token == other.token
+    }
+}
+
+impl PartialEq<Token> for SpannedToken {
+    /// This function compares a spanned token to a token, ignoring the span and spacing.
+    /// ```
+    /// use token_stream2::Token;
+    ///
+    /// let stream: token_stream2::TokenStream = "a, b".parse().unwrap();
+    /// let peeked = stream.peek(1).unwrap();
+    /// assert!(peeked == Token::Comma);
+    /// assert!(peeked != Token::Semi);
+    /// ```
+    fn eq(&self, other: &Token) -> bool {
+        &self.token == other
+    }
+}
+
+impl PartialEq<SpannedToken> for Token {
+    /// This function compares a token to a spanned token, ignoring the span and spacing.
+    /// ```
+    /// use token_stream2::Token;
+    ///
+    /// let stream: token_stream2::TokenStream = "a, b".parse().unwrap();
+    /// assert!(Token::Ident("a".into()) == stream[0]);
+    /// assert!(Token::Ident("b".into()) != stream[0]);
+    /// ```
+    fn eq(&self, other: &SpannedToken) -> bool {
+        self == &other.token
+    }
+}
+
+/// This struct is a `SpannedToken` without its span, since spans can't be serialized.
+/// It is only available with the `serde` feature enabled.
+/// Floats are serialized as `f64`, so they round-trip exactly through formats that support every `f64`. JSON can't represent NaN or infinity.
+/// ```
+/// use token_stream2::{SpannedToken, UnspannedToken};
+///
+/// let stream: token_stream2::TokenStream = "a += 0.1".parse().unwrap();
+/// let unspanned: Vec<UnspannedToken> = stream.clone().map(|t| UnspannedToken::from(&t)).collect();
+///
+/// let json = serde_json::to_string(&unspanned).unwrap();
+/// let restored: Vec<UnspannedToken> = serde_json::from_str(&json).unwrap();
+/// assert!(restored == unspanned);
+/// assert!(restored[1].joint);
+///
+/// let restored: Vec<SpannedToken> = restored.into_iter().map(SpannedToken::from).collect();
+/// assert!(restored == stream.collect::<Vec<_>>());
+/// ```
+#[cfg(feature = "serde")]
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct UnspannedToken {
+    /// The token itself.
+    pub token: Token,
+    /// Whether the token is punctuation immediately followed by more punctuation.
+    pub joint: bool,
+}
+
+#[cfg(feature = "serde")]
+impl From<&SpannedToken> for UnspannedToken {
+    fn from(value: &SpannedToken) -> Self {
+        UnspannedToken {
+            token: value.token.clone(),
+            joint: value.spacing == Some(proc_macro2::Spacing::Joint),
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl From<UnspannedToken> for SpannedToken {
+    /// This function restores a `SpannedToken`, using `Span::call_site()` as its span.
+    fn from(value: UnspannedToken) -> Self {
+        let spacing = match (value.token.is_punct(), value.joint) {
+            (false, _) => None,
+            (true, true) => Some(proc_macro2::Spacing::Joint),
+            (true, false) => Some(proc_macro2::Spacing::Alone),
+        };
+        SpannedToken {
+            token: value.token,
+            span: proc_macro2::Span::call_site(),
+            spacing,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct TokenStream {
+    // The tokens are shared between clones, which only copy the cursor. `Rc::make_mut` copies them on write.
+    tokens: std::rc::Rc<Vec<SpannedToken>>,
+    iter_ptr: usize,
+    // This is the number of tokens taken from the back of the stream by `next_back()`.
+    back_ptr: usize,
+    // This is the `#!` line that `FromStr` removed from the start of the source, if there was one.
+    shebang: Option<String>,
+}
+
+/// This is a saved cursor position of a `TokenStream`, created by `TokenStream::checkpoint()`.
+/// It can be handed back to `TokenStream::restore()` to undo everything consumed since.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Checkpoint {
+    iter_ptr: usize,
+    back_ptr: usize,
+}
+
+/// This struct is a read-only position in the tokens of a `TokenStream`, created by `TokenStream::cursor()`.
+/// Moving a cursor never changes the stream it borrows from, and copying one is a cheap way to try a parse and throw it away.
+/// ```
+/// use token_stream2::Token;
+///
+/// let stream: token_stream2::TokenStream = "a + b".parse().unwrap();
+/// let mut cursor = stream.cursor();
+/// assert!(cursor.bump().unwrap().token() == &Token::Ident("a".into()));
+///
+/// let mut fork = cursor;
+/// assert!(fork.bump().unwrap().token() == &Token::Plus);
+/// assert!(fork.bump().unwrap().token() == &Token::Ident("b".into()));
+/// assert!(fork.eof());
+///
+/// assert!(cursor.peek().unwrap().token() == &Token::Plus);
+/// assert!(cursor.position() == 1);
+/// assert!(stream.position() == 0);
+/// ```
+#[derive(Clone, Copy, Debug)]
+pub struct Cursor<'a> {
+    tokens: &'a [SpannedToken],
+    position: usize,
+}
+
+impl<'a> Cursor<'a> {
+    /// This function returns the token at the cursor without moving it.
+    pub fn peek(&self) -> Option<&'a SpannedToken> {
+        self.tokens.get(self.position)
+    }
+    /// This function returns the token at the cursor, then moves the cursor forward by one.
+    pub fn bump(&mut self) -> Option<&'a SpannedToken> {
+        let token = self.tokens.get(self.position)?;
+        self.position += 1;
+        Some(token)
+    }
+    /// This function returns whether the cursor is past the last token.
+    pub fn eof(&self) -> bool {
+        self.position >= self.tokens.len()
+    }
+    /// This function returns the index of the token at the cursor.
+    pub fn position(&self) -> usize {
+        self.position
+    }
+}
+
+/// This struct represents an attribute found by `TokenStream::attributes()`, such as `#[derive(Clone)]` or `#![no_std]`.
+/// The ranges are absolute token indices into the stream, like `TokenStream::position()`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Attribute {
+    inner: bool,
+    range: std::ops::Range<usize>,
+    content: std::ops::Range<usize>,
+}
+
+impl Attribute {
+    /// This function returns whether the attribute is an inner attribute, written `#![...]`, rather than an outer one, written `#[...]`.
+    pub fn is_inner(&self) -> bool {
+        self.inner
+    }
+    /// This function returns the indices of the whole attribute, from the `#` up to and including the closing `]`.
+    pub fn range(&self) -> std::ops::Range<usize> {
+        self.range.clone()
+    }
+    /// This function returns the indices of the tokens between the brackets.
+    pub fn content(&self) -> std::ops::Range<usize> {
+        self.content.clone()
+    }
+}
+
+/// This enum represents a token in the nested view of a `TokenStream` built by `TokenStream::to_tree()`.
+#[derive(Clone, Debug)]
+pub enum TokenNode {
+    /// Represents any token that isn't a delimiter.
+    Leaf(SpannedToken),
+    /// Represents a pair of delimiters and every token between them.
+    Group {
+        /// This is the kind of delimiter around the group.
+        delimiter: proc_macro2::Delimiter,
+        /// This is the span of the opening delimiter.
+        span: proc_macro2::Span,
+        /// These are the tokens between the delimiters, with nested groups rebuilt too.
+        children: Vec<TokenNode>,
+    },
+}
+
+/// This trait is implemented by everything that carries a span, so helpers like error constructors can accept any of them.
+/// ```
+/// use token_stream2::{ParseError, Spanned, TokenNode, TokenStream};
+///
+/// fn unexpected(node: &impl Spanned) -> ParseError {
+///     ParseError::new(*node.span(), "unexpected token")
+/// }
+///
+/// let stream: TokenStream = "a (b)".parse().unwrap();
+/// let tree = stream.to_tree().unwrap();
+/// assert!(matches!(tree[1], TokenNode::Group { .. }));
+/// assert!(unexpected(&stream[0]).span().start().column == 0);
+/// assert!(unexpected(&tree[1]).span().start().column == 2);
+/// assert!(unexpected(&unexpected(&stream[2])).span().start().column == 3);
+/// ```
+pub trait Spanned {
+    /// This function returns the span of the value.
+    fn span(&self) -> &proc_macro2::Span;
+}
+
+impl Spanned for SpannedToken {
+    fn span(&self) -> &proc_macro2::Span {
+        &self.span
+    }
+}
+
+impl Spanned for TokenNode {
+    /// This function returns the span of a leaf's token, or the span of a group's opening delimiter.
+    fn span(&self) -> &proc_macro2::Span {
+        match self {
+            TokenNode::Leaf(token) => &token.span,
+            TokenNode::Group { span, .. } => span,
+        }
+    }
+}
+
+impl Spanned for ParseError {
+    fn span(&self) -> &proc_macro2::Span {
+        &self.span
+    }
+}
+
+impl Spanned for ConvertError {
+    fn span(&self) -> &proc_macro2::Span {
+        ConvertError::span(self)
+    }
+}
+
+/// This trait walks over the tokens of a `TokenStream`, see `TokenStream::accept()`.
+/// Every hook does nothing by default, so a visitor only needs to implement the ones it cares about.
+/// Each hook also receives the delimiter depth of the token: top-level tokens are at depth 0,
+/// and the tokens inside a group are one deeper than its delimiters.
+/// ```
+/// use token_stream2::{SpannedToken, TokenStream, Visitor};
+///
+/// #[derive(Default)]
+/// struct IdentCounter {
+///     idents: usize,
+///     deepest: usize,
+/// }
+///
+/// impl Visitor for IdentCounter {
+///     fn visit_ident(&mut self, _token: &SpannedToken, depth: usize) {
+///         self.idents += 1;
+///         self.deepest = self.deepest.max(depth);
+///     }
+/// }
+///
+/// let stream: TokenStream = "fn main() { let x = (y, 1); }".parse().unwrap();
+/// let mut counter = IdentCounter::default();
+/// stream.accept(&mut counter);
+/// assert!(counter.idents == 5);
+/// assert!(counter.deepest == 2);
+/// ```
+pub trait Visitor {
+    /// This function is called for every token. By default, it calls the hook for the token's category below.
+    fn visit_token(&mut self, token: &SpannedToken, depth: usize) {
+        match token.kind() {
+            TokenKind::Ident | TokenKind::Underscore => self.visit_ident(token, depth),
+            TokenKind::Lifetime => self.visit_lifetime(token, depth),
+            TokenKind::Punct => self.visit_punct(token, depth),
+            TokenKind::Delimiter if token.token().is_open_delim() => {
+                self.visit_open_delim(token, depth)
+            }
+            TokenKind::Delimiter => self.visit_close_delim(token, depth),
+            TokenKind::None => {}
+            _ => self.visit_literal(token, depth),
+        }
+    }
+    /// This function is called for every `Token::Ident`, `Token::RawIdent` and `Token::Underscore`.
+    fn visit_ident(&mut self, _token: &SpannedToken, _depth: usize) {}
+    /// This function is called for every `Token::Lifetime`.
+    fn visit_lifetime(&mut self, _token: &SpannedToken, _depth: usize) {}
+    /// This function is called for every literal, including `Token::Bool` and `Token::Literal`.
+    fn visit_literal(&mut self, _token: &SpannedToken, _depth: usize) {}
+    /// This function is called for every punctuation token, including multi-character operators.
+    fn visit_punct(&mut self, _token: &SpannedToken, _depth: usize) {}
+    /// This function is called for every opening delimiter, at the depth outside of its group.
+    fn visit_open_delim(&mut self, _token: &SpannedToken, _depth: usize) {}
+    /// This function is called for every closing delimiter, at the depth outside of its group.
+    fn visit_close_delim(&mut self, _token: &SpannedToken, _depth: usize) {}
+}
+
+impl std::fmt::Display for TokenStream {
+    /// This function renders every token in the stream as approximate source code, regardless of the cursor position.
+    /// Braces and semicolons start new lines, and punctuation that was joint in the source is kept together.
+    /// Re-parsing the output produces the same tokens, except that invisible groups are lost.
+    /// ```
+    /// let to_parse: proc_macro2::TokenStream = r#"
+    ///      fn main() {
+    ///          let x = a::b(1, 2);
+    ///          println!("{}", x);
+    ///      }
+    ///  "#
+    ///  .parse()
+    ///  .expect("infallible");
+    ///
+    /// let stream: token_stream2::TokenStream = to_parse.into();
+    /// let rendered = stream.to_string();
+    /// assert!(rendered.starts_with("fn main() {\n    let x = a::b(1, 2);\n"));
+    ///
+    /// let reparsed: proc_macro2::TokenStream = rendered.parse().expect("infallible");
+    /// let reparsed: token_stream2::TokenStream = reparsed.into();
+    /// assert!(
+    ///     reparsed.map(|t| t.token().clone()).collect::<Vec<_>>()
+    ///         == stream.map(|t| t.token().clone()).collect::<Vec<_>>()
+    /// );
+    /// ```
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut indent = 0;
+        let mut line_start = true;
+        let mut previous: Option<&SpannedToken> = None;
+        for token in self.tokens.iter() {
+            if matches!(
+                token.token,
+                Token::OpenNone | Token::CloseNone | Token::None
+            ) {
+                continue;
+            }
+            if token.token == Token::CloseBrace {
+                indent = usize::saturating_sub(indent, 1);
+                if !line_start {
+                    writeln!(f)?;
+                    line_start = true;
+                }
+            }
+            if line_start {
+                write!(f, "{:1$}", "", indent * 4)?;
+            } else if previous.map_or(false, |previous| needs_space(previous, token)) {
+                f.write_str(" ")?;
+            }
+            write!(f, "{}", token.token)?;
+            line_start = false;
+            match token.token {
+                Token::OpenBrace => {
+                    indent += 1;
+                    writeln!(f)?;
+                    line_start = true;
+                }
+                Token::CloseBrace | Token::Semi => {
+                    writeln!(f)?;
+                    line_start = true;
+                }
+                _ => {}
+            }
+            previous = Some(token);
+        }
+        Ok(())
+    }
+}
+
+// This function decides whether a space is needed between two tokens when rendering a stream.
+// Spaces are only left out where doing so can't change how the source is tokenized.
+fn needs_space(previous: &SpannedToken, token: &SpannedToken) -> bool {
+    if previous.spacing == Some(proc_macro2::Spacing::Joint) {
+        return false;
+    }
+    match (&previous.token, &token.token) {
+        (Token::OpenParen | Token::OpenBracket, _) => false,
+        (_, Token::CloseParen | Token::CloseBracket) => false,
+        (Token::Ident(..) | Token::RawIdent(..), Token::OpenParen | Token::OpenBracket) => false,
+        (Token::Ident(..) | Token::RawIdent(..), Token::Bang) => false,
+        (Token::Bang, Token::OpenParen | Token::OpenBracket) => false,
+        (previous, Token::Comma | Token::Semi | Token::PathSep) => previous.is_punct(),
+        (Token::PathSep, token) => token.is_punct(),
+        _ => true,
+    }
+}
+
+impl Iterator for TokenStream {
+    type Item = SpannedToken;
+
+    /// This function returns the token at the cursor, then moves the cursor forward by one.
+    /// The first call returns the first token of the stream.
+    /// ```
+    /// let to_parse: proc_macro2::TokenStream = r#"
+    ///      fn main() {}
+    ///  "#
+    ///  .parse()
+    ///  .expect("infallible");
+    ///
+    /// let stream: token_stream2::TokenStream = to_parse.into();
+    /// let tokens: Vec<token_stream2::SpannedToken> = stream.collect();
+    /// assert!(tokens.len() == 6);
+    /// assert!(tokens[0].token() == &token_stream2::Token::Ident("fn".to_string()));
+    /// assert!(tokens[5].token() == &token_stream2::Token::CloseBrace);
+    /// ```
+    fn next(&mut self) -> Option<Self::Item> {
+        let x = self.current().cloned();
+        if x.is_some() {
+            self.iter_ptr += 1;
+        }
+        x
+    }
+
+    /// This function returns the exact number of tokens left between the cursor and the back of the stream.
+    /// ```
+    /// let mut stream: token_stream2::TokenStream = "fn main() {}".parse().unwrap();
+    /// assert!(stream.size_hint() == (6, Some(6)));
+    /// stream.next();
+    /// assert!(stream.size_hint() == (5, Some(5)));
+    /// stream.next_back();
+    /// assert!(stream.size_hint() == (4, Some(4)));
+    /// stream.seek(5);
+    /// assert!(stream.size_hint() == (0, Some(0)));
+    /// stream.reset();
+    /// assert!(stream.size_hint() == (6, Some(6)));
+    /// ```
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.end().saturating_sub(self.iter_ptr);
+        (remaining, Some(remaining))
+    }
+}
+
+/// `ExactSizeIterator::len` returns the number of tokens left to iterate over, while the inherent
+/// `TokenStream::len` returns the number of tokens in the whole stream.
+/// ```
+/// use std::iter::ExactSizeIterator;
+///
+/// let mut stream: token_stream2::TokenStream = "a b c".parse().unwrap();
+/// stream.next();
+/// assert!(stream.len() == 3);
+/// assert!(ExactSizeIterator::len(&stream) == 2);
+/// assert!(ExactSizeIterator::len(&stream) == stream.clone().count());
+/// ```
+impl ExactSizeIterator for TokenStream {}
+
+impl DoubleEndedIterator for TokenStream {
+    /// This function returns the last token that hasn't been returned yet, from either end.
+    /// The front and back of the stream never cross, so every token is returned at most once.
+    /// ```
+    /// let mut stream: token_stream2::TokenStream = "a b c d".parse().unwrap();
+    /// let ident = |name: &str| token_stream2::Token::Ident(name.into());
+    /// assert!(stream.next_back().unwrap().token() == &ident("d"));
+    /// assert!(stream.next().unwrap().token() == &ident("a"));
+    /// assert!(stream.next_back().unwrap().token() == &ident("c"));
+    /// assert!(stream.peek(1).is_none());
+    /// assert!(stream.next().unwrap().token() == &ident("b"));
+    /// assert!(stream.next_back().is_none());
+    /// assert!(stream.next().is_none());
+    /// ```
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.iter_ptr >= self.end() {
+            return None;
+        }
+        self.back_ptr += 1;
+        self.tokens.get(self.end()).cloned()
+    }
+}
+
+impl TokenStream {
+    /// This function allows you to peek ahead in the tokenstream's iterator.
+    /// Useful for quickly parsing what's next.
+    /// ```
+    /// let to_parse: proc_macro2::TokenStream = r#"
+    ///      fn main() {
+    ///          println!("Hello world!");
+    ///      }
+    ///  "#
+    ///  .parse()
+    ///  .expect("infallible");
+    ///
+    /// let mut stream: token_stream2::TokenStream = to_parse.into();
+    /// assert!(stream.peek(2).unwrap().token() == &token_stream2::Token::OpenParen);
+    /// assert!(stream.peek(3).unwrap().token() == &token_stream2::Token::CloseParen);
+    /// ```
+    /// `peek(0)` is always the token that the next call to `next()` will return, including `None` at the end of the stream.
+    /// ```
+    /// let to_parse: proc_macro2::TokenStream = "a + (b)".parse().expect("infallible");
+    ///
+    /// let mut stream: token_stream2::TokenStream = to_parse.into();
+    /// for _ in 0..7 {
+    ///     let peeked = stream.peek(0).map(|t| t.token().clone());
+    ///     let next = stream.next().map(|t| t.token().clone());
+    ///     assert!(peeked == next);
+    /// }
+    /// assert!(stream.peek(0).is_none());
+    /// ```
+    pub fn peek(&self, ahead: usize) -> Option<SpannedToken> {
+        self.peek_ref(ahead).cloned()
+    }
+
+    /// This function works like `peek()`, but borrows the token from the stream instead of cloning it.
+    /// ```
+    /// let stream: token_stream2::TokenStream = "a + b".parse().unwrap();
+    /// let peeked: &token_stream2::SpannedToken = stream.peek_ref(1).unwrap();
+    /// assert!(peeked.token() == &token_stream2::Token::Plus);
+    /// assert!(std::ptr::eq(peeked, &stream[1]));
+    /// assert!(stream.peek_ref(3).is_none());
+    /// ```
+    pub fn peek_ref(&self, ahead: usize) -> Option<&SpannedToken> {
+        let index = self.iter_ptr.checked_add(ahead)?;
+        if index >= self.end() {
+            return None;
+        }
+        self.tokens.get(index)
+    }
+
+    /// This function works like `peek_ref()`, but only returns the token, which is all most parsers need to look at.
+    /// ```
+    /// use token_stream2::Token;
+    ///
+    /// let mut stream: token_stream2::TokenStream = "let x = 1;".parse().unwrap();
+    /// let mut idents = 0;
+    /// while let Some(token) = stream.peek_token(0) {
+    ///     if token.is_ident() {
+    ///         idents += 1;
+    ///     }
+    ///     stream.next();
+    /// }
+    /// assert!(idents == 2);
+    /// assert!(stream.peek_token(0).is_none());
+    /// ```
+    pub fn peek_token(&self, ahead: usize) -> Option<&Token> {
+        self.peek_ref(ahead).map(SpannedToken::token)
+    }
+
+    /// This function works like `peek_token()`, but only returns the category of the token. See `Token::kind()`.
+    /// ```
+    /// use token_stream2::TokenKind;
+    ///
+    /// let stream: token_stream2::TokenStream = "x = \"hi\";".parse().unwrap();
+    /// assert!(stream.peek_kind(0) == Some(TokenKind::Ident));
+    /// assert!(stream.peek_kind(1) == Some(TokenKind::Punct));
+    /// assert!(stream.peek_kind(2) == Some(TokenKind::String));
+    /// assert!(stream.peek_kind(3) == Some(TokenKind::Punct));
+    /// assert!(stream.peek_kind(4) == None);
+    /// assert!(stream.peek_kind(usize::MAX) == None);
+    /// ```
+    pub fn peek_kind(&self, ahead: usize) -> Option<TokenKind> {
+        self.peek_ref(ahead).map(SpannedToken::kind)
+    }
+
+    /// This function allows you to look behind the cursor at tokens that were already consumed.
+    /// `peek_back(1)` is the token most recently returned by `next()`.
+    /// ```
+    /// let to_parse: proc_macro2::TokenStream = "a, b".parse().expect("infallible");
+    ///
+    /// let mut stream: token_stream2::TokenStream = to_parse.into();
+    /// assert!(stream.peek_back(1).is_none());
+    /// stream.next();
+    /// stream.next();
+    /// assert!(stream.peek_back(1).unwrap().token() == &token_stream2::Token::Comma);
+    /// assert!(stream.peek_back(2).unwrap().token() == &token_stream2::Token::Ident("a".into()));
+    /// assert!(stream.peek_back(3).is_none());
+    /// ```
+    pub fn peek_back(&self, behind: usize) -> Option<SpannedToken> {
+        let index = self.iter_ptr.checked_sub(behind)?;
+        self.tokens.get(index).cloned()
+    }
+
+    /// This function creates an empty stream. Tokens can be added with `TokenStream::push()` or `Extend`.
+    /// ```
+    /// let mut stream = token_stream2::TokenStream::new();
+    /// assert!(stream.is_empty());
+    /// assert!(stream.next().is_none());
+    /// ```
+    pub fn new() -> Self {
+        TokenStream::with_tokens(vec![])
+    }
+
+    /// This function creates a stream out of the given tokens, with the cursor at the start.
+    /// This is mostly useful for testing parsers without going through `proc_macro2`.
+    /// ```
+    /// use proc_macro2::Span;
+    /// use token_stream2::{SpannedToken, Token, TokenStream};
+    ///
+    /// let mut stream = TokenStream::with_tokens(vec![
+    ///     SpannedToken::new(Token::Ident("x".into()), Span::call_site()),
+    ///     SpannedToken::new(Token::Equal, Span::call_site()),
+    ///     SpannedToken::new(Token::Integer(1), Span::call_site()),
+    /// ]);
+    /// assert!(stream.next().unwrap().token() == &Token::Ident("x".into()));
+    /// assert!(stream.next().unwrap().token() == &Token::Equal);
+    /// assert!(stream.next().unwrap().token() == &Token::Integer(1));
+    /// assert!(stream.next().is_none());
+    /// ```
+    pub fn with_tokens(tokens: Vec<SpannedToken>) -> Self {
+        TokenStream {
+            tokens: std::rc::Rc::new(tokens),
+            iter_ptr: 0,
+            back_ptr: 0,
+            shebang: None,
+        }
+    }
+
+    /// This function returns the shebang line, such as `#!/usr/bin/env run-cargo-script`, if the stream was tokenized from source text that started with one.
+    /// The line is not part of the tokens. Streams built any other way never have a shebang.
+    ///
+    /// Like in `rustc`, a leading `#!` followed by `[` is an inner attribute rather than a shebang,
+    /// even with whitespace or comments other than doc comments in between.
+    /// ```
+    /// use token_stream2::{Token, TokenStream};
+    ///
+    /// let stream: TokenStream = "#!/usr/bin/env run-cargo-script\nfn main() {}".parse().unwrap();
+    /// assert!(stream.shebang() == Some("#!/usr/bin/env run-cargo-script"));
+    /// assert!(stream.peek_token(0) == Some(&Token::Ident("fn".into())));
+    ///
+    /// let stream: TokenStream = "#![no_std]\nfn main() {}".parse().unwrap();
+    /// assert!(stream.shebang().is_none());
+    /// assert!(stream.peek_token(0) == Some(&Token::Hash));
+    ///
+    /// let stream: TokenStream = "#!/*c*/[no_std]".parse().unwrap();
+    /// assert!(stream.shebang().is_none());
+    /// assert!(stream.len() == 5 && stream.peek_token(0) == Some(&Token::Hash));
+    ///
+    /// let stream: TokenStream = "#! // note\n[allow(unused)] fn f() {}".parse().unwrap();
+    /// assert!(stream.shebang().is_none());
+    /// assert!(stream.peek_sequence(&[Token::Hash, Token::Bang, Token::OpenBracket]));
+    ///
+    /// let stream: TokenStream = "fn main() {}".parse().unwrap();
+    /// assert!(stream.shebang().is_none());
+    /// ```
+    pub fn shebang(&self) -> Option<&str> {
+        self.shebang.as_deref()
+    }
+
+    /// This function converts a `proc_macro2::TokenStream` into a `token_stream2::TokenStream` without panicking.
+    /// This is what the `From` implementation uses internally, a procedural macro can use it to report a `compile_error!` instead of aborting.
+    ///
+    /// This is an inherent function rather than a `TryFrom<proc_macro2::TokenStream>` implementation, because `From` is already implemented
+    /// and the standard library's blanket `impl<T, U: Into<T>> TryFrom<U> for T` would conflict with it.
+    /// `TokenStream::try_from()` still exists through that blanket impl, but its error is `Infallible`, since it goes through `From`.
+    /// Every literal `proc_macro2` accepts can currently be converted, see `ConvertError::InvalidLiteral`, but a macro should still handle the error.
+    /// ```
+    /// use token_stream2::{ConvertError, TokenStream};
+    ///
+    /// fn expand(input: proc_macro2::TokenStream) -> proc_macro2::TokenStream {
+    ///     let stream = match TokenStream::try_convert(input) {
+    ///         Ok(stream) => stream,
+    ///         Err(err) => return err.to_compile_error(),
+    ///     };
+    ///     proc_macro2::TokenStream::try_from(stream).unwrap_or_else(|err| err.to_compile_error())
+    /// }
+    ///
+    /// let to_parse: proc_macro2::TokenStream = r"foo b'\n'".parse().expect("infallible");
+    /// assert!(expand(to_parse).to_string() == r"foo b'\n'");
+    ///
+    /// let err: ConvertError = "foo (".parse::<TokenStream>().unwrap_err();
+    /// assert!(err.to_compile_error().to_string().contains("compile_error"));
+    /// ```
+    /// Nested groups are flattened in source order, with each delimiter keeping the span of its group.
+    /// ```
+    /// use token_stream2::Token;
+    ///
+    /// let to_parse: proc_macro2::TokenStream = "a ( [ { b } c ] ) d".parse().expect("infallible");
+    /// let stream = token_stream2::TokenStream::try_convert(to_parse).unwrap();
+    /// let tokens: Vec<Token> = stream.iter().map(|t| t.token().clone()).collect();
+    /// assert!(tokens == vec![
+    ///     Token::Ident("a".into()),
+    ///     Token::OpenParen,
+    ///     Token::OpenBracket,
+    ///     Token::OpenBrace,
+    ///     Token::Ident("b".into()),
+    ///     Token::CloseBrace,
+    ///     Token::Ident("c".into()),
+    ///     Token::CloseBracket,
+    ///     Token::CloseParen,
+    ///     Token::Ident("d".into()),
+    /// ]);
+    /// let columns: Vec<usize> = stream.iter().map(|t| t.span().start().column).collect();
+    /// assert!(columns == vec![0, 2, 4, 6, 8, 6, 12, 4, 2, 18]);
+    /// ```
+    /// The conversion doesn't recurse into groups, so even very deeply nested input can't overflow the stack.
+    /// ```
+    /// let depth = 100_000;
+    /// let source = format!("{}x{}", "(".repeat(depth), ")".repeat(depth));
+    /// let to_parse: proc_macro2::TokenStream = source.parse().expect("infallible");
+    ///
+    /// let stream = token_stream2::TokenStream::try_convert(to_parse).unwrap();
+    /// assert!(stream.len() == depth * 2 + 1);
+    /// assert!(stream[depth].token() == &token_stream2::Token::Ident("x".into()));
+    /// assert!(stream.matching_delimiter(0) == Some(depth * 2));
+    /// ```
+    pub fn try_convert(tokens: proc_macro2::TokenStream) -> Result<TokenStream, ConvertError> {
+        TokenStream::from_with_options(tokens, ConvertOptions::default())
+    }
+
+    /// This function converts a `proc_macro2::TokenStream` into a `token_stream2::TokenStream`, with `options` controlling which optional tokens are produced.
+    /// ```
+    /// use token_stream2::{ConvertOptions, Token, TokenStream};
+    ///
+    /// let to_parse: proc_macro2::TokenStream = "true r#true".parse().expect("infallible");
+    ///
+    /// let options = ConvertOptions::new().bool_literals(true);
+    /// let stream = TokenStream::from_with_options(to_parse, options).unwrap();
+    /// let tokens: Vec<Token> = stream.map(|t| t.token().clone()).collect();
+    /// assert!(tokens == vec![Token::Bool(true), Token::RawIdent("true".into())]);
+    /// ```
+    /// Options can be combined, and each one only changes the tokens it is about.
+    /// ```
+    /// use token_stream2::{ConvertOptions, Token, TokenStream};
+    ///
+    /// let to_parse: proc_macro2::TokenStream = "f(-1, false)".parse().expect("infallible");
+    /// let convert = |options: ConvertOptions| -> Vec<Token> {
+    ///     let stream = TokenStream::from_with_options(to_parse.clone(), options).unwrap();
+    ///     stream.map(|t| t.token().clone()).collect()
+    /// };
+    ///
+    /// let plain = convert(ConvertOptions::new());
+    /// assert!(plain[2..5] == [Token::Minus, Token::Integer(1), Token::Comma]);
+    /// assert!(plain[5] == Token::Ident("false".into()));
+    ///
+    /// let both = convert(ConvertOptions::new().signed_numbers(true).bool_literals(true));
+    /// assert!(both[2..5] == [Token::Integer(-1), Token::Comma, Token::Bool(false)]);
+    /// ```
+    pub fn from_with_options(
+        tokens: proc_macro2::TokenStream,
+        options: ConvertOptions,
+    ) -> Result<TokenStream, ConvertError> {
+        LazyTokenStream::with_options(tokens, options)
+            .collect::<Result<Vec<_>, _>>()
+            .map(TokenStream::with_tokens)
+    }
+
+    /// This function returns the number of tokens in the stream, including the ones the cursor has already moved past.
+    /// Since the stream is flattened, every opening and closing delimiter counts as a token.
+    /// Use `ExactSizeIterator::len` for the number of tokens left to iterate over.
+    /// ```
+    /// let stream: token_stream2::TokenStream = "fn main() {}".parse().unwrap();
+    /// assert!(stream.len() == 6);
+    /// assert!(stream.len() == stream.clone().count());
+    /// ```
+    pub fn len(&self) -> usize {
+        self.tokens.len()
+    }
+
+    /// This function returns whether the stream has no tokens at all.
+    /// ```
+    /// let stream: token_stream2::TokenStream = "".parse().unwrap();
+    /// assert!(stream.is_empty());
+    /// ```
+    pub fn is_empty(&self) -> bool {
+        self.tokens.is_empty()
+    }
+
+    /// This function returns the token at `index`, regardless of the cursor position.
+    /// Indices are positions in the flattened stream, and don't change as the cursor moves.
+    /// ```
+    /// let stream: token_stream2::TokenStream = "a(b)".parse().unwrap();
+    /// assert!(stream.get(2).unwrap().token() == &token_stream2::Token::Ident("b".into()));
+    /// assert!(stream.get(4).is_none());
+    /// ```
+    pub fn get(&self, index: usize) -> Option<&SpannedToken> {
+        self.tokens.get(index)
+    }
+
+    /// This function returns one span covering the tokens from `start` up to, but not including, `end`.
+    /// It returns `None` if the range is empty or out of bounds.
+    ///
+    /// The spans are combined with `Span::join`. Inside a procedural macro, that only works on a nightly compiler,
+    /// so on stable this falls back to the span of the first token in the range.
+    /// Outside of a procedural macro, joining works as long as both spans come from the same source.
+    /// ```
+    /// let stream: token_stream2::TokenStream = "let answer = 42;".parse().unwrap();
+    /// let span = stream.span_of_range(1, 3).unwrap();
+    /// assert!(span.start().column == 4);
+    /// assert!(span.end().column == 12);
+    /// assert!(stream.span_of_range(2, 2).is_none());
+    /// assert!(stream.span_of_range(3, 6).is_none());
+    /// ```
+    pub fn span_of_range(&self, start: usize, end: usize) -> Option<proc_macro2::Span> {
+        let range = self.tokens.get(start..end)?;
+        let first = range.first()?.span;
+        let last = range.last()?.span;
+        Some(first.join(last).unwrap_or(first))
+    }
+
+    /// This function returns one span covering the whole stream, regardless of the cursor position, or `None` if the stream is empty.
+    /// It joins the spans of the first and last tokens, and falls back to the span of the first token where joining isn't possible, see `TokenStream::span_of_range()`.
+    /// ```
+    /// let stream: token_stream2::TokenStream = "struct Foo;".parse().unwrap();
+    /// let span = stream.span().unwrap();
+    /// assert!(span.start().column == 0);
+    /// assert!(span.end().column == 11);
+    ///
+    /// assert!(token_stream2::TokenStream::new().span().is_none());
+    /// ```
+    pub fn span(&self) -> Option<proc_macro2::Span> {
+        self.span_of_range(0, self.tokens.len())
+    }
+
+    /// This function creates a `Cursor` at the first token of the stream, regardless of the cursor position. See `Cursor` for an example.
+    pub fn cursor(&self) -> Cursor<'_> {
+        Cursor {
+            tokens: &self.tokens,
+            position: 0,
+        }
+    }
+
+    /// This function returns every token in the stream as a slice, regardless of the cursor position.
+    pub fn as_slice(&self) -> &[SpannedToken] {
+        &self.tokens
+    }
+
+    /// This function returns an iterator that borrows every token in the stream, without cloning them or moving the cursor.
+    /// ```
+    /// let mut stream: token_stream2::TokenStream = "a + b".parse().unwrap();
+    /// stream.next();
+    /// let idents = stream.iter().filter(|t| t.token().is_ident()).count();
+    /// assert!(idents == 2);
+    /// assert!(stream.position() == 1);
+    /// assert!(stream.as_slice().len() == 3);
+    /// ```
+    pub fn iter(&self) -> std::slice::Iter<'_, SpannedToken> {
+        self.tokens.iter()
+    }
+
+    /// This function returns the tokens that haven't been consumed yet, from the cursor up to any tokens taken with `next_back()`.
+    /// ```
+    /// let mut stream: token_stream2::TokenStream = "a b c".parse().unwrap();
+    /// assert!(stream.remaining().len() == 3);
+    /// stream.next();
+    /// assert!(stream.remaining().len() == 2);
+    /// assert!(stream.remaining()[0].token() == &token_stream2::Token::Ident("b".into()));
+    /// stream.next_back();
+    /// assert!(stream.remaining().len() == 1);
+    /// stream.next();
+    /// assert!(stream.remaining().is_empty());
+    /// ```
+    pub fn remaining(&self) -> &[SpannedToken] {
+        let end = self.end();
+        &self.tokens[self.iter_ptr.min(end)..end]
+    }
+
+    /// This function returns the number of tokens that haven't been consumed yet. See `TokenStream::remaining()`.
+    /// ```
+    /// let mut stream: token_stream2::TokenStream = "x = 1;".parse().unwrap();
+    /// assert!(stream.remaining_count() == 4);
+    /// stream.seek(4);
+    /// assert!(stream.remaining_count() == 0);
+    /// ```
+    pub fn remaining_count(&self) -> usize {
+        self.remaining().len()
+    }
+
+    /// This function returns the position of the cursor, which is the index of the token that `next()` will return.
+    /// Positions are indices into the flattened stream, so delimiters count as tokens.
+    pub fn position(&self) -> usize {
+        self.iter_ptr
+    }
+
+    /// This function moves the cursor to `pos`, so the next call to `next()` returns the token at that index.
+    /// Combined with `TokenStream::position()`, this allows a parser to backtrack.
+    ///
+    /// # Panics
+    /// This function panics if `pos` is past the end of the stream.
+    /// ```
+    /// let to_parse: proc_macro2::TokenStream = "a b c d".parse().expect("infallible");
+    ///
+    /// let mut stream: token_stream2::TokenStream = to_parse.into();
+    /// stream.next();
+    /// let pos = stream.position();
+    /// assert!(pos == 1);
+    /// let b = stream.next().unwrap();
+    /// stream.next();
+    /// stream.seek(pos);
+    /// assert!(stream.next().unwrap().token() == b.token());
+    /// stream.reset();
+    /// assert!(stream.position() == 0);
+    /// ```
+    pub fn seek(&mut self, pos: usize) {
+        assert!(
+            pos <= self.tokens.len(),
+            "seek position {} is out of bounds for a stream of {} tokens",
+            pos,
+            self.tokens.len()
+        );
+        self.iter_ptr = pos;
+    }
+
+    /// This function moves the cursor forward by `n` tokens without returning them.
+    /// If the stream ends first, the cursor stops at the end and the number of tokens that couldn't be skipped is returned as the error.
+    /// ```
+    /// use token_stream2::Token;
+    ///
+    /// let mut stream: token_stream2::TokenStream = "::std::vec".parse().unwrap();
+    /// assert!(stream.advance_by(1) == Ok(()));
+    /// assert!(stream.next().unwrap().token() == &Token::Ident("std".into()));
+    /// assert!(stream.advance_by(0) == Ok(()));
+    /// assert!(stream.advance_by(3) == Err(1));
+    /// assert!(stream.next().is_none());
+    /// ```
+    pub fn advance_by(&mut self, n: usize) -> Result<(), usize> {
+        let remaining = self.end().saturating_sub(self.iter_ptr);
+        if n <= remaining {
+            self.iter_ptr += n;
+            Ok(())
+        } else {
+            self.iter_ptr += remaining;
+            Err(n - remaining)
+        }
+    }
+
+    /// This function saves the cursor, from both ends of the stream, so a parser can backtrack with `TokenStream::restore()`.
+    /// ```
+    /// use token_stream2::Token;
+    ///
+    /// let mut stream: token_stream2::TokenStream = "a b c".parse().unwrap();
+    /// stream.next();
+    /// let checkpoint = stream.checkpoint();
+    /// stream.next();
+    /// stream.next_back();
+    /// assert!(stream.next().is_none());
+    ///
+    /// stream.restore(checkpoint);
+    /// assert!(stream.next().unwrap().token() == &Token::Ident("b".into()));
+    /// assert!(stream.next().unwrap().token() == &Token::Ident("c".into()));
+    /// ```
+    pub fn checkpoint(&self) -> Checkpoint {
+        Checkpoint {
+            iter_ptr: self.iter_ptr,
+            back_ptr: self.back_ptr,
+        }
+    }
+
+    /// This function moves the cursor back to where it was when `checkpoint` was created.
+    /// Tokens added with `Extend` since then are kept.
+    pub fn restore(&mut self, checkpoint: Checkpoint) {
+        self.iter_ptr = checkpoint.iter_ptr;
+        self.back_ptr = checkpoint.back_ptr;
+    }
+
+    /// This function creates a copy of the stream with its own cursor, for trying a branch of a parser.
+    /// Consuming tokens from the fork doesn't affect the original, so the branch can simply be dropped if it fails.
+    /// Forking is cheap, as the fork shares its tokens with the original until either of them is extended.
+    /// ```
+    /// use token_stream2::Token;
+    ///
+    /// let mut stream: token_stream2::TokenStream = "a: b".parse().unwrap();
+    /// let mut fork = stream.fork();
+    /// assert!(fork.next().unwrap().token() == &Token::Ident("a".into()));
+    /// assert!(fork.expect(&Token::Semi).is_err());
+    ///
+    /// assert!(stream.position() == 0);
+    /// assert!(stream.peek(1).unwrap().token() == &Token::Colon);
+    /// ```
+    /// Clones work the same way, each one iterating on its own.
+    /// ```
+    /// use token_stream2::Token;
+    ///
+    /// let mut first: token_stream2::TokenStream = "a b".parse().unwrap();
+    /// let mut second = first.clone();
+    /// second.extend(vec![Token::Semi]);
+    /// assert!(second.next().unwrap().token() == &Token::Ident("a".into()));
+    /// assert!(second.next().unwrap().token() == &Token::Ident("b".into()));
+    ///
+    /// assert!(first.next().unwrap().token() == &Token::Ident("a".into()));
+    /// assert!(second.next().unwrap().token() == &Token::Semi);
+    /// assert!(first.next().unwrap().token() == &Token::Ident("b".into()));
+    /// assert!(first.next().is_none());
+    /// ```
+    pub fn fork(&self) -> TokenStream {
+        self.clone()
+    }
+
+    /// This function moves the cursor back to the start of the stream.
+    /// Tokens taken from the back with `next_back()` become available again too.
+    pub fn reset(&mut self) {
+        self.iter_ptr = 0;
+        self.back_ptr = 0;
+    }
+
+    /// This function appends a single token to the stream, like `Extend<SpannedToken>`.
+    /// ```
+    /// use token_stream2::Token;
+    ///
+    /// let mut stream: token_stream2::TokenStream = "a b c".parse().unwrap();
+    /// let last = stream.next_back().unwrap();
+    /// stream.push(last);
+    /// assert!(stream.len() == 4);
+    /// stream.seek(2);
+    /// assert!(stream.next().unwrap().token() == &Token::Ident("c".into()));
+    /// assert!(stream.next().is_none());
+    /// ```
+    pub fn push(&mut self, token: SpannedToken) {
+        self.extend(std::iter::once(token));
+    }
+
+    /// This function appends every token of `other` to the stream, like `Extend<SpannedToken>`, keeping their spans.
+    /// The cursor of the stream is preserved. Unlike extending with `other` as an iterator,
+    /// the tokens `other`'s cursor has already moved past are appended too.
+    /// ```
+    /// use token_stream2::Token;
+    ///
+    /// let mut stream: token_stream2::TokenStream = "a b".parse().unwrap();
+    /// let mut other: token_stream2::TokenStream = "c".parse().unwrap();
+    /// other.next();
+    /// stream.next();
+    /// stream.append(other);
+    /// assert!(stream.position() == 1);
+    /// assert!(stream.remaining_count() == 2);
+    /// assert!(stream[2].token() == &Token::Ident("c".into()));
+    /// ```
+    pub fn append(&mut self, other: TokenStream) {
+        self.extend(other.tokens.iter().cloned());
+    }
+
+    /// This function joins two streams into one, like `TokenStream::append()`. The cursor of `self` is preserved.
+    /// ```
+    /// use token_stream2::Token;
+    ///
+    /// let first: token_stream2::TokenStream = "let x".parse().unwrap();
+    /// let second: token_stream2::TokenStream = "= 1;".parse().unwrap();
+    /// let joined = first.concat(second);
+    /// assert!(joined.len() == 5);
+    /// assert!(joined[4].span().start().column == 3);
+    ///
+    /// let tokens: Vec<Token> = joined.map(|t| t.token().clone()).collect();
+    /// assert!(tokens == vec![Token::Ident("let".into()), Token::Ident("x".into()), Token::Equal, Token::Integer(1), Token::Semi]);
+    /// ```
+    pub fn concat(mut self, other: TokenStream) -> TokenStream {
+        self.append(other);
+        self
+    }
+
+    // This function returns the index just past the last token that hasn't been taken from the back of the stream.
+    fn end(&self) -> usize {
+        self.tokens.len().saturating_sub(self.back_ptr)
+    }
+
+    // This function returns the token under the cursor, which is the token `next()` will return.
+    fn current(&self) -> Option<&SpannedToken> {
+        if self.iter_ptr >= self.end() {
+            return None;
+        }
+        self.tokens.get(self.iter_ptr)
+    }
+
+    /// This function advances past the next token if it is equal to `token`, and returns an error otherwise.
+    /// The cursor does not move if the token doesn't match.
+    /// ```
+    /// use token_stream2::Token;
+    ///
+    /// let to_parse: proc_macro2::TokenStream = "(a)".parse().expect("infallible");
+    ///
+    /// let mut stream: token_stream2::TokenStream = to_parse.into();
+    /// assert!(stream.expect(&Token::OpenParen).is_ok());
+    /// let err = stream.expect(&Token::CloseParen).unwrap_err();
+    /// assert!(err.expected() == Some(&Token::CloseParen));
+    /// assert!(err.found() == Some(&Token::Ident("a".into())));
+    /// assert!(stream.position() == 1);
+    /// ```
+    pub fn expect(&mut self, token: &Token) -> Result<SpannedToken, ParseError> {
+        match self.consume_if(token) {
+            Some(found) => Ok(found),
+            None => Err(self
+                .unexpected(format!("expected `{}`", token))
+                .with_expected(token.clone())),
+        }
+    }
+
+    /// This function advances past the next token if its category is any of `kinds`, and returns an error listing all of them otherwise.
+    /// The cursor does not move if the token doesn't match.
+    /// ```
+    /// use token_stream2::{Token, TokenKind};
+    ///
+    /// let mut stream: token_stream2::TokenStream = "\"name\" ;".parse().unwrap();
+    /// let found = stream.expect_one_of(&[TokenKind::Ident, TokenKind::String]).unwrap();
+    /// assert!(found.token() == &Token::String("name".into()));
+    ///
+    /// let err = stream.expect_one_of(&[TokenKind::Ident, TokenKind::String]).unwrap_err();
+    /// assert!(err.message() == "expected one of: identifier, string, found `;`");
+    /// assert!(err.expected_kinds().unwrap().contains(TokenKind::Ident));
+    /// assert!(err.found() == Some(&Token::Semi));
+    /// assert!(stream.position() == 1);
+    /// ```
+    pub fn expect_one_of(&mut self, kinds: &[TokenKind]) -> Result<SpannedToken, ParseError> {
+        match self.current() {
+            Some(found) if kinds.contains(&found.kind()) => {
+                let found = found.clone();
+                self.iter_ptr += 1;
+                Ok(found)
+            }
+            _ => {
+                let kinds: ExpectedSet = kinds.iter().copied().collect();
+                Err(self
+                    .unexpected(format!("expected one of: {}", kinds))
+                    .with_expected_kinds(kinds))
+            }
+        }
+    }
+
+    /// This function advances past the next token only if it is equal to `token`.
+    /// ```
+    /// use token_stream2::Token;
+    ///
+    /// let to_parse: proc_macro2::TokenStream = "a, b".parse().expect("infallible");
+    ///
+    /// let mut stream: token_stream2::TokenStream = to_parse.into();
+    /// assert!(stream.consume_if(&Token::Comma).is_none());
+    /// stream.next();
+    /// assert!(stream.consume_if(&Token::Comma).unwrap().token() == &Token::Comma);
+    /// ```
+    pub fn consume_if(&mut self, token: &Token) -> Option<SpannedToken> {
+        match self.current() {
+            Some(found) if found.token() == token => self.next(),
+            _ => None,
+        }
+    }
+
+    /// This function returns whether the tokens at the cursor are equal to `pattern`, in order, without moving the cursor.
+    /// Only the tokens are compared, spans are ignored. An empty pattern always matches.
+    /// ```
+    /// use token_stream2::Token;
+    ///
+    /// let mut stream: token_stream2::TokenStream = "&mut self".parse().unwrap();
+    /// let mut_self = [Token::Ident("mut".into()), Token::Ident("self".into())];
+    /// assert!(!stream.peek_sequence(&mut_self));
+    /// stream.next();
+    /// assert!(stream.peek_sequence(&mut_self));
+    /// stream.next();
+    /// assert!(!stream.peek_sequence(&mut_self));
+    /// ```
+    pub fn peek_sequence(&self, pattern: &[Token]) -> bool {
+        let remaining = self.remaining();
+        remaining.len() >= pattern.len()
+            && remaining
+                .iter()
+                .zip(pattern)
+                .all(|(found, expected)| found.token() == expected)
+    }
+
+    /// This function advances past the tokens at the cursor if they are equal to `pattern`, and returns whether they were.
+    /// The cursor only moves if the whole pattern matches.
+    /// ```
+    /// use token_stream2::Token;
+    ///
+    /// let mut stream: token_stream2::TokenStream = "a => b =".parse().unwrap();
+    /// assert!(!stream.consume_sequence(&[Token::Ident("a".into()), Token::Equal]));
+    /// assert!(stream.position() == 0);
+    /// assert!(stream.consume_sequence(&[Token::Ident("a".into()), Token::FatArrow]));
+    /// assert!(stream.position() == 2);
+    ///
+    /// stream.next();
+    /// assert!(!stream.consume_sequence(&[Token::Equal, Token::Equal]));
+    /// assert!(stream.consume_sequence(&[Token::Equal]));
+    /// assert!(stream.next().is_none());
+    /// ```
+    pub fn consume_sequence(&mut self, pattern: &[Token]) -> bool {
+        if !self.peek_sequence(pattern) {
+            return false;
+        }
+        self.iter_ptr += pattern.len();
+        true
+    }
+
+    /// This function returns whether the tokens at the cursor are identifiers with the given names, in order, without moving the cursor.
+    /// It works like `TokenStream::peek_sequence()`, without building a `Token::Ident` for every name. Raw identifiers don't match.
+    /// ```
+    /// let stream: token_stream2::TokenStream = "pub fn main() {}".parse().unwrap();
+    /// assert!(stream.starts_with_idents(&["pub", "fn"]));
+    /// assert!(!stream.starts_with_idents(&["pub", "struct"]));
+    /// assert!(!stream.starts_with_idents(&["pub", "fn", "main", "x"]));
+    ///
+    /// let stream: token_stream2::TokenStream = "pub r#fn".parse().unwrap();
+    /// assert!(!stream.starts_with_idents(&["pub", "fn"]));
+    /// ```
+    pub fn starts_with_idents(&self, names: &[&str]) -> bool {
+        let remaining = self.remaining();
+        remaining.len() >= names.len()
+            && remaining
+                .iter()
+                .zip(names)
+                .all(|(found, name)| matches!(&found.token, Token::Ident(ident) if ident == name))
+    }
+
+    /// This function collects tokens until it reaches `stop` or the end of the stream, leaving the cursor on the `stop` token.
+    /// The `stop` token itself is not included.
+    /// ```
+    /// use token_stream2::Token;
+    ///
+    /// let mut stream: token_stream2::TokenStream = "a b; c".parse().unwrap();
+    /// let collected = stream.collect_until(&Token::Semi);
+    /// assert!(collected.len() == 2);
+    /// assert!(stream.peek(0).unwrap().token() == &Token::Semi);
+    ///
+    /// stream.next();
+    /// let rest = stream.collect_until(&Token::Semi);
+    /// assert!(rest.len() == 1);
+    /// assert!(stream.peek(0).is_none());
+    /// ```
+    pub fn collect_until(&mut self, stop: &Token) -> Vec<SpannedToken> {
+        let mut collected = vec![];
+        while let Some(token) = self.current() {
+            if token.token() == stop {
+                break;
+            }
+            collected.push(token.clone());
+            self.iter_ptr += 1;
+        }
+        collected
+    }
+
+    /// This function moves the cursor past every token that matches `pred`, stopping on the first token that doesn't.
+    /// At the end of the stream it simply stops. It returns the number of tokens that were skipped.
+    ///
+    /// This isn't called `skip_while` because `stream.skip_while(..)` would call `Iterator::skip_while` instead.
+    /// ```
+    /// use token_stream2::Token;
+    ///
+    /// let mut stream: token_stream2::TokenStream = "a,,, b".parse().unwrap();
+    /// stream.next();
+    /// assert!(stream.skip_tokens_while(|token| token == &Token::Comma) == 3);
+    /// assert!(stream.next().unwrap().token() == &Token::Ident("b".into()));
+    /// assert!(stream.skip_tokens_while(|_| true) == 0);
+    /// ```
+    pub fn skip_tokens_while(&mut self, pred: impl Fn(&Token) -> bool) -> usize {
+        let start = self.iter_ptr;
+        while let Some(token) = self.current() {
+            if !pred(token.token()) {
+                break;
+            }
+            self.iter_ptr += 1;
+        }
+        self.iter_ptr - start
+    }
+
+    /// This function collects every token that matches `pred`, leaving the cursor on the first token that doesn't.
+    /// At the end of the stream it returns what was collected so far, which may be nothing.
+    ///
+    /// This isn't called `take_while` because `stream.take_while(..)` would call `Iterator::take_while` instead.
+    /// ```
+    /// use token_stream2::Token;
+    ///
+    /// let mut stream: token_stream2::TokenStream = "a b c = d".parse().unwrap();
+    /// let idents = stream.take_tokens_while(Token::is_ident);
+    /// assert!(idents.len() == 3);
+    /// assert!(stream.peek(0).unwrap().token() == &Token::Equal);
+    /// assert!(stream.take_tokens_while(Token::is_ident).is_empty());
+    ///
+    /// stream.next();
+    /// assert!(stream.take_tokens_while(Token::is_ident).len() == 1);
+    /// assert!(stream.next().is_none());
+    /// ```
+    pub fn take_tokens_while(&mut self, pred: impl Fn(&Token) -> bool) -> Vec<SpannedToken> {
+        let mut collected = vec![];
+        while let Some(token) = self.current() {
+            if !pred(token.token()) {
+                break;
+            }
+            collected.push(token.clone());
+            self.iter_ptr += 1;
+        }
+        collected
+    }
+
+    /// This function returns the index of the next token equal to `token`, searching from the cursor without moving it.
+    /// The index is absolute, like `TokenStream::position()`.
+    /// ```
+    /// use token_stream2::Token;
+    ///
+    /// let mut stream: token_stream2::TokenStream = "a, b, c".parse().unwrap();
+    /// assert!(stream.position_of(&Token::Comma) == Some(1));
+    /// stream.seek(2);
+    /// assert!(stream.position_of(&Token::Comma) == Some(3));
+    /// assert!(stream.position_of(&Token::Semi) == None);
+    /// ```
+    pub fn position_of(&self, token: &Token) -> Option<usize> {
+        let remaining = self.tokens.get(self.iter_ptr..self.end())?;
+        remaining
+            .iter()
+            .position(|found| found.token() == token)
+            .map(|offset| self.iter_ptr + offset)
+    }
+
+    /// This function works like `TokenStream::position_of()`, but only matches tokens at the same delimiter depth as the cursor.
+    /// Tokens inside nested groups are skipped, and the search stops at the closing delimiter of the group the cursor is in.
+    /// ```
+    /// use token_stream2::Token;
+    ///
+    /// let mut stream: token_stream2::TokenStream = "(f(a, b), c) , d".parse().unwrap();
+    /// stream.next();
+    /// assert!(stream.position_of(&Token::Comma) == Some(4));
+    /// assert!(stream.position_of_at_depth(&Token::Comma) == Some(7));
+    /// stream.seek(8);
+    /// assert!(stream.position_of_at_depth(&Token::Comma) == None);
+    /// stream.reset();
+    /// assert!(stream.position_of_at_depth(&Token::Comma) == Some(10));
+    /// ```
+    pub fn position_of_at_depth(&self, token: &Token) -> Option<usize> {
+        let remaining = self.tokens.get(self.iter_ptr..self.end())?;
+        let mut depth = 0usize;
+        for (offset, found) in remaining.iter().enumerate() {
+            if depth == 0 && found.token() == token {
+                return Some(self.iter_ptr + offset);
+            }
+            if found.token().is_open_delim() {
+                depth += 1;
+            } else if found.token().is_close_delim() {
+                if depth == 0 {
+                    return None;
+                }
+                depth -= 1;
+            }
+        }
+        None
+    }
+
+    /// This function moves the cursor past the next token equal to `token` and returns it.
+    /// If there is no such token, it returns `None` and the cursor doesn't move.
+    /// ```
+    /// use token_stream2::Token;
+    ///
+    /// let mut stream: token_stream2::TokenStream = "a = b; c".parse().unwrap();
+    /// assert!(stream.find_next(&Token::Semi).unwrap().token() == &Token::Semi);
+    /// assert!(stream.peek(0).unwrap().token() == &Token::Ident("c".into()));
+    /// assert!(stream.find_next(&Token::Semi).is_none());
+    /// assert!(stream.position() == 4);
+    /// ```
+    pub fn find_next(&mut self, token: &Token) -> Option<SpannedToken> {
+        let index = self.position_of(token)?;
+        self.iter_ptr = index;
+        self.next()
+    }
+
+    /// This function splits the tokens from the cursor to the end of the stream on every `sep` that isn't inside a nested group, without moving the cursor.
+    /// Like `str::split`, separators at the start or end, or next to each other, produce empty segments, so there is always one more segment than there are separators.
+    /// ```
+    /// use token_stream2::Token;
+    ///
+    /// let stream: token_stream2::TokenStream = "a, (b, c), d".parse().unwrap();
+    /// let segments = stream.split_on(&Token::Comma);
+    /// assert!(segments.len() == 3);
+    /// assert!(segments[0].len() == 1);
+    /// assert!(segments[1].len() == 5);
+    /// assert!(segments[2][0].token() == &Token::Ident("d".into()));
+    ///
+    /// let stream: token_stream2::TokenStream = ", a,, b,".parse().unwrap();
+    /// let lengths: Vec<usize> = stream.split_on(&Token::Comma).iter().map(Vec::len).collect();
+    /// assert!(lengths == vec![0, 1, 0, 1, 0]);
+    /// ```
+    pub fn split_on(&self, sep: &Token) -> Vec<Vec<SpannedToken>> {
+        let mut segments = vec![vec![]];
+        let mut depth = 0usize;
+        for token in self
+            .tokens
+            .get(self.iter_ptr..self.end())
+            .unwrap_or_default()
+        {
+            if depth == 0 && token.token() == sep {
+                segments.push(vec![]);
+                continue;
+            }
+            if token.token().is_open_delim() {
+                depth += 1;
+            } else if token.token().is_close_delim() {
+                depth = depth.saturating_sub(1);
+            }
+            if let Some(segment) = segments.last_mut() {
+                segment.push(token.clone());
+            }
+        }
+        segments
+    }
+
+    /// This function returns every token in the stream of the given category, regardless of the cursor position, without moving it.
+    /// ```
+    /// use token_stream2::{Token, TokenKind};
+    ///
+    /// let stream: token_stream2::TokenStream = "fn add(a: i32, b: i32) -> i32 { a + b }".parse().unwrap();
+    /// let idents: Vec<Token> = stream
+    ///     .tokens_of_kind(TokenKind::Ident)
+    ///     .into_iter()
+    ///     .map(|t| t.token().clone())
+    ///     .collect();
+    /// assert!(idents.len() == 9);
+    /// assert!(idents[0] == Token::Ident("fn".into()));
+    /// assert!(idents[1] == Token::Ident("add".into()));
+    /// assert!(stream.tokens_of_kind(TokenKind::String).is_empty());
+    /// assert!(stream.position() == 0);
+    /// ```
+    pub fn tokens_of_kind(&self, kind: TokenKind) -> Vec<SpannedToken> {
+        self.tokens
+            .iter()
+            .filter(|token| token.kind() == kind)
+            .cloned()
+            .collect()
+    }
+
+    /// This function counts the tokens of the given category in the stream, regardless of the cursor position.
+    /// Unlike `TokenStream::tokens_of_kind()`, it doesn't clone the tokens it finds.
+    /// ```
+    /// use token_stream2::{Token, TokenKind};
+    ///
+    /// let stream: token_stream2::TokenStream = "f(g(1), (h()), \"s\")".parse().unwrap();
+    /// let open_parens = stream.iter().filter(|t| t.token() == &Token::OpenParen).count();
+    /// assert!(open_parens == 4);
+    /// assert!(stream.count_kind(TokenKind::Delimiter) == open_parens * 2);
+    /// assert!(stream.count_kind(TokenKind::Ident) == 3);
+    /// assert!(stream.count_kind(TokenKind::String) == 1);
+    /// assert!(stream.count_kind(TokenKind::Float) == 0);
+    /// ```
+    pub fn count_kind(&self, kind: TokenKind) -> usize {
+        self.tokens
+            .iter()
+            .filter(|token| token.kind() == kind)
+            .count()
+    }
+
+    /// This function creates a new stream out of the tokens matching `pred`, keeping their spans, with the cursor at the start.
+    /// Every token in the stream is considered, regardless of the cursor position.
+    ///
+    /// Filtering out delimiters can leave the new stream unbalanced, see `TokenStream::is_balanced()`.
+    /// ```
+    /// use token_stream2::Token;
+    ///
+    /// let stream: token_stream2::TokenStream = "f(a, b, c)".parse().unwrap();
+    /// let filtered = stream.filter_tokens(|token| token != &Token::Comma);
+    /// assert!(filtered.len() == 6);
+    /// assert!(filtered.iter().all(|token| token.token() != &Token::Comma));
+    /// assert!(filtered.is_balanced());
+    ///
+    /// let unbalanced = stream.filter_tokens(|token| token != &Token::CloseParen);
+    /// assert!(!unbalanced.is_balanced());
+    /// ```
+    pub fn filter_tokens(&self, pred: impl Fn(&Token) -> bool) -> TokenStream {
+        self.tokens
+            .iter()
+            .filter(|token| pred(token.token()))
+            .cloned()
+            .collect()
+    }
+
+    /// This function creates a new stream without any `Token::None` sentinels, with the cursor at the start.
+    /// Only `Token::None` is removed. The invisible delimiters `Token::OpenNone` and `Token::CloseNone` are real groups and are kept,
+    /// so the new stream is balanced whenever the original one is.
+    /// Streams converted from `proc_macro2` never contain `Token::None`, so this is only needed for streams built by hand.
+    /// ```
+    /// use token_stream2::Token;
+    ///
+    /// let stream: token_stream2::TokenStream = vec![
+    ///     Token::None,
+    ///     Token::OpenNone,
+    ///     Token::Ident("a".into()),
+    ///     Token::None,
+    ///     Token::CloseNone,
+    /// ]
+    /// .into_iter()
+    /// .collect();
+    /// let compacted = stream.compact();
+    /// assert!(compacted.len() == 3);
+    /// assert!(compacted[0].token() == &Token::OpenNone);
+    /// assert!(compacted[1].token() == &Token::Ident("a".into()));
+    /// assert!(compacted.matching_delimiter(0) == Some(2));
+    /// ```
+    pub fn compact(&self) -> TokenStream {
+        self.filter_tokens(|token| !matches!(token, Token::None))
+    }
+
+    /// This function creates a new stream where every `Token::Ident` is renamed by `f`, with the cursor at the start.
+    /// Spans are kept, and every other token, including `Token::RawIdent`, is left untouched.
+    /// ```
+    /// use token_stream2::Token;
+    ///
+    /// let stream: token_stream2::TokenStream = r#"let x = "x" + 1;"#.parse().unwrap();
+    /// let renamed = stream.map_idents(|name| name.to_uppercase());
+    /// let tokens: Vec<Token> = renamed.map(|t| t.token().clone()).collect();
+    /// assert!(tokens == vec![
+    ///     Token::Ident("LET".into()),
+    ///     Token::Ident("X".into()),
+    ///     Token::Equal,
+    ///     Token::String("x".into()),
+    ///     Token::Plus,
+    ///     Token::Integer(1),
+    ///     Token::Semi,
+    /// ]);
+    /// ```
+    pub fn map_idents(&self, f: impl Fn(&str) -> String) -> TokenStream {
+        self.tokens
+            .iter()
+            .map(|token| match &token.token {
+                Token::Ident(name) => SpannedToken {
+                    token: Token::Ident(f(name)),
+                    span: token.span,
+                    spacing: token.spacing,
+                },
+                _ => token.clone(),
+            })
+            .collect()
+    }
+
+    /// This function rebuilds the groups of the stream from its delimiters, regardless of the cursor position.
+    /// The stream itself is left flat, so both views can be used side by side.
+    /// An error is returned if the stream isn't balanced, see `TokenStream::is_balanced()`.
+    /// ```
+    /// use token_stream2::{Token, TokenNode, TokenStream};
+    ///
+    /// let stream: TokenStream = "f(a, [b, c]) d".parse().unwrap();
+    /// let tree = stream.to_tree().unwrap();
+    /// assert!(tree.len() == 3);
+    /// match &tree[1] {
+    ///     TokenNode::Group { delimiter, children, .. } => {
+    ///         assert!(*delimiter == proc_macro2::Delimiter::Parenthesis);
+    ///         assert!(children.len() == 3);
+    ///         assert!(matches!(&children[2], TokenNode::Group { children, .. } if children.len() == 3));
+    ///     }
+    ///     TokenNode::Leaf(_) => panic!("expected a group"),
+    /// }
+    /// assert!(matches!(&tree[2], TokenNode::Leaf(token) if token.token() == &Token::Ident("d".into())));
+    ///
+    /// let unclosed: TokenStream = vec![Token::OpenParen, Token::Ident("a".into())].into_iter().collect();
+    /// assert!(unclosed.to_tree().unwrap_err().message() == "unclosed delimiter `(`");
+    /// ```
+    pub fn to_tree(&self) -> Result<Vec<TokenNode>, ParseError> {
+        let mut root = vec![];
+        let mut open_groups: Vec<(proc_macro2::Delimiter, &SpannedToken, Vec<TokenNode>)> = vec![];
+        for token in self.tokens.iter() {
+            if let Some(delimiter) = open_delimiter(&token.token) {
+                open_groups.push((delimiter, token, vec![]));
+                continue;
+            }
+            let node = match close_delimiter(&token.token) {
+                Some(delimiter) => match open_groups.pop() {
+                    Some((open, open_token, children)) if open == delimiter => TokenNode::Group {
+                        delimiter,
+                        span: open_token.span,
+                        children,
+                    },
+                    Some(_) => {
+                        return Err(ParseError::new(
+                            token.span,
+                            format!("mismatched closing delimiter `{}`", token.token),
+                        )
+                        .with_found(token.token.clone()))
+                    }
+                    None => {
+                        return Err(ParseError::new(
+                            token.span,
+                            format!("unexpected closing delimiter `{}`", token.token),
+                        )
+                        .with_found(token.token.clone()))
+                    }
+                },
+                None => TokenNode::Leaf(token.clone()),
+            };
+            match open_groups.last_mut() {
+                Some((_, _, children)) => children.push(node),
+                None => root.push(node),
+            }
+        }
+        match open_groups.pop() {
+            Some((_, open_token, _)) => Err(ParseError::new(
+                open_token.span,
+                format!("unclosed delimiter `{}`", open_token.token),
+            )),
+            None => Ok(root),
+        }
+    }
+
+    /// This function creates a new stream where signs in front of numbers are folded into them, with the cursor at the start.
+    /// For example `-5` becomes a single `Token::Integer(-5)` instead of `Token::Minus` followed by `Token::Integer(5)`.
+    ///
+    /// Whether a sign belongs to a number depends on the grammar being parsed, so this is an opt-in heuristic:
+    /// a `-` or `+` is folded into a following integer or float when it is at the start of the stream,
+    /// or right after an opening delimiter, any punctuation other than the postfix `?`, or a keyword that is followed by an expression,
+    /// like `return`, `in` or `if`. After any other identifier, a literal, a closing delimiter or a `?`, the sign is treated as a binary operator and left alone.
+    /// Whitespace isn't significant, so `- 5` is folded like `-5`.
+    /// Numbers with an unsigned suffix, like `5u8`, are never folded. `-170141183460469231731687303715884105728` folds into `Token::Integer(i128::MIN)`,
+    /// even though its digits alone are a `Token::BigInteger`, and likewise with an `i128` suffix.
+    /// ```
+    /// use token_stream2::Token;
+    ///
+    /// let fold = |source: &str| -> Vec<Token> {
+    ///     let stream: token_stream2::TokenStream = source.parse().unwrap();
+    ///     stream.fold_signed_numbers().map(|t| t.token().clone()).collect()
+    /// };
+    /// assert!(fold("-5") == vec![Token::Integer(-5)]);
+    /// assert!(fold("- 5") == vec![Token::Integer(-5)]);
+    /// assert!(fold("+2.5") == vec![Token::Float(2.5)]);
+    /// assert!(fold("a - 5") == vec![Token::Ident("a".into()), Token::Minus, Token::Integer(5)]);
+    /// assert!(fold("x? - 1") == vec![Token::Ident("x".into()), Token::Question, Token::Minus, Token::Integer(1)]);
+    /// assert!(fold("return -1") == vec![Token::Ident("return".into()), Token::Integer(-1)]);
+    /// assert!(fold("for i in -5..5 {}")[3] == Token::Integer(-5));
+    /// assert!(fold("-170141183460469231731687303715884105728") == vec![Token::Integer(i128::MIN)]);
+    /// assert!(fold("-170141183460469231731687303715884105728i128") == vec![Token::IntegerSuffixed { value: i128::MIN, suffix: "i128".into() }]);
+    /// assert!(fold("(1) - 5") == vec![Token::OpenParen, Token::Integer(1), Token::CloseParen, Token::Minus, Token::Integer(5)]);
+    /// assert!(fold("f(-1, x * -2i8)")[2..4] == [Token::Integer(-1), Token::Comma]);
+    /// assert!(fold("x * -2i8")[2] == Token::IntegerSuffixed { value: -2, suffix: "i8".into() });
+    /// assert!(fold("-2u8") == vec![Token::Minus, Token::IntegerSuffixed { value: 2, suffix: "u8".into() }]);
+    /// ```
+    pub fn fold_signed_numbers(&self) -> TokenStream {
+        let mut output: Vec<SpannedToken> = vec![];
+        let mut tokens = self.tokens.iter().peekable();
+        while let Some(token) = tokens.next() {
+            let negate = match token.token {
+                Token::Minus => true,
+                Token::Plus => false,
+                _ => {
+                    output.push(token.clone());
+                    continue;
+                }
+            };
+            let operand_position = output
+                .last()
+                .map_or(true, |previous| starts_operand(&previous.token));
+            let folded = match tokens.peek() {
+                Some(number) if operand_position => signed_number(&number.token, negate),
+                _ => None,
+            };
+            match folded {
+                Some(folded) => {
+                    let number = tokens.next().map_or(token.span, |number| number.span);
+                    output.push(SpannedToken {
+                        token: folded,
+                        span: token.span.join(number).unwrap_or(token.span),
+                        spacing: None,
+                    });
+                }
+                None => output.push(token.clone()),
+            }
+        }
+        TokenStream::with_tokens(output)
+    }
+
+    /// This function checks that rendering the stream with `Display` and tokenizing the result again gives back the same tokens.
+    /// It is meant for tests and debug assertions, to catch tokens that can't be written back as source code.
+    /// It is hidden from the documentation because it is a testing aid rather than part of the parsing API,
+    /// and what it tolerates may change as more tokens keep the exact text they were written as.
+    ///
+    /// Tokens are compared by how they render, ignoring spans and spacing, which tolerates the differences that don't matter in source code.
+    /// Floats and raw strings don't have to keep the exact text they were written as, `Token::Bool` and `Token::Underscore` match the identifiers they stand for,
+    /// and signs are folded into numbers on both sides first, see `TokenStream::fold_signed_numbers()`.
+    /// Invisible delimiters and `Token::None` render as nothing, so they are skipped.
+    /// ```
+    /// use token_stream2::{Token, TokenStream};
+    ///
+    /// let stream: TokenStream = r###"fn main() { let x = [1.50, 2e3]; println!(r#"{}"#, -x[0]); }"###.parse().unwrap();
+    /// assert!(stream.verify_roundtrip());
+    ///
+    /// let quoted: TokenStream = vec![Token::String("say \"hi\"".into())].into_iter().collect();
+    /// assert!(!quoted.verify_roundtrip());
+    /// ```
+    #[doc(hidden)]
+    pub fn verify_roundtrip(&self) -> bool {
+        let reparsed = match self.to_string().parse::<proc_macro2::TokenStream>() {
+            Ok(reparsed) => reparsed,
+            Err(..) => return false,
+        };
+        let reparsed = match TokenStream::from_with_options(
+            reparsed,
+            ConvertOptions::new().float_text(true),
+        ) {
+            Ok(reparsed) => reparsed,
+            Err(..) => return false,
+        };
+        let rendered = |stream: &TokenStream| -> Vec<String> {
+            stream
+                .fold_signed_numbers()
+                .tokens
+                .iter()
+                .filter(|token| {
+                    !matches!(
+                        token.token,
+                        Token::OpenNone | Token::CloseNone | Token::None
+                    )
+                })
+                .map(|token| token.token.to_string())
+                .collect()
+        };
+        rendered(self) == rendered(&reparsed)
+    }
+
+    /// This function pairs every token in the stream that isn't a delimiter with its delimiter depth, regardless of the cursor position.
+    /// Top-level tokens are at depth 0, and the tokens inside a group are one deeper than the group itself.
+    /// The delimiters are left out, as the depths already say where each group starts and ends.
+    /// ```
+    /// let stream: token_stream2::TokenStream = "a { b { c } d } e".parse().unwrap();
+    /// let depths: Vec<(String, usize)> = stream
+    ///     .with_depths()
+    ///     .into_iter()
+    ///     .map(|(token, depth)| (token.token().to_string(), depth))
+    ///     .collect();
+    /// assert!(
+    ///     depths
+    ///         == vec![
+    ///             ("a".to_string(), 0),
+    ///             ("b".to_string(), 1),
+    ///             ("c".to_string(), 2),
+    ///             ("d".to_string(), 1),
+    ///             ("e".to_string(), 0),
+    ///         ]
+    /// );
+    /// ```
+    pub fn with_depths(&self) -> Vec<(SpannedToken, usize)> {
+        let mut depths = vec![];
+        let mut depth = 0usize;
+        for token in self.tokens.iter() {
+            if token.token().is_open_delim() {
+                depth += 1;
+            } else if token.token().is_close_delim() {
+                depth = depth.saturating_sub(1);
+            } else {
+                depths.push((token.clone(), depth));
+            }
+        }
+        depths
+    }
+
+    /// This function groups every token in the stream by the line its span starts on, regardless of the cursor position.
+    /// Lines start at 1, and the tokens on each line are kept in stream order.
+    /// Delimiters carry the span of their whole group, so a closing delimiter is grouped with the line its group opens on.
+    /// Tokens whose span has no location, which happens inside a procedural macro on compilers that don't expose it, are left out.
+    /// This function requires the `span-locations` feature.
+    /// ```
+    /// let to_parse: proc_macro2::TokenStream = "let a = 1;\nlet b = (a,\n    2);".parse().expect("infallible");
+    ///
+    /// let stream: token_stream2::TokenStream = to_parse.into();
+    /// let lines = stream.line_spans();
+    /// let text = |line: usize| -> Vec<String> { lines[&line].iter().map(|t| t.token().to_string()).collect() };
+    /// assert!(lines.keys().copied().collect::<Vec<_>>() == vec![1, 2, 3]);
+    /// assert!(text(1) == vec!["let", "a", "=", "1", ";"]);
+    /// assert!(text(2) == vec!["let", "b", "=", "(", "a", ",", ")"]);
+    /// assert!(text(3) == vec!["2", ";"]);
+    /// ```
+    #[cfg(feature = "span-locations")]
+    pub fn line_spans(&self) -> std::collections::BTreeMap<usize, Vec<SpannedToken>> {
+        let mut lines: std::collections::BTreeMap<usize, Vec<SpannedToken>> = Default::default();
+        for token in self.tokens.iter() {
+            if let Some((start, _)) = token.location() {
+                lines.entry(start.line).or_default().push(token.clone());
+            }
+        }
+        lines
+    }
+
+    /// This function finds every attribute in the stream, at any depth, regardless of the cursor position.
+    /// An attribute is a `#`, optionally followed by a `!` for inner attributes, followed by a bracketed group.
+    /// Attributes inside the brackets of another attribute aren't searched for.
+    /// ```
+    /// let stream: token_stream2::TokenStream = "#![no_std] #[derive(Clone)] struct Foo;".parse().unwrap();
+    /// let attributes = stream.attributes();
+    /// assert!(attributes.len() == 2);
+    ///
+    /// assert!(attributes[0].is_inner());
+    /// assert!(attributes[0].range() == (0..5));
+    /// assert!(attributes[0].content() == (3..4));
+    /// assert!(stream.get(3).unwrap().token() == &token_stream2::Token::Ident("no_std".into()));
+    ///
+    /// assert!(!attributes[1].is_inner());
+    /// assert!(attributes[1].range() == (5..12));
+    /// let content: Vec<String> = stream.as_slice()[attributes[1].content()]
+    ///     .iter()
+    ///     .map(|t| t.token().to_string())
+    ///     .collect();
+    /// assert!(content == vec!["derive", "(", "Clone", ")"]);
+    /// ```
+    pub fn attributes(&self) -> Vec<Attribute> {
+        let mut attributes = vec![];
+        let mut index = 0;
+        while index < self.tokens.len() {
+            if self.tokens[index].token != Token::Hash {
+                index += 1;
+                continue;
+            }
+            let inner = self.tokens.get(index + 1).map(|t| &t.token) == Some(&Token::Bang);
+            let open = index + 1 + usize::from(inner);
+            match self.tokens.get(open).map(|t| &t.token) {
+                Some(Token::OpenBracket) => {}
+                _ => {
+                    index += 1;
+                    continue;
+                }
+            }
+            match self.group_end(open) {
+                Ok(close) => {
+                    attributes.push(Attribute {
+                        inner,
+                        range: index..close + 1,
+                        content: open + 1..close,
+                    });
+                    index = close + 1;
+                }
+                Err(..) => index += 1,
+            }
+        }
+        attributes
+    }
+
+    /// This function passes every token in the stream to `visitor`, regardless of the cursor position.
+    /// See `Visitor` for an example.
+    pub fn accept(&self, visitor: &mut impl Visitor) {
+        let mut depth = 0usize;
+        for token in self.tokens.iter() {
+            if token.token().is_close_delim() {
+                depth = depth.saturating_sub(1);
+            }
+            visitor.visit_token(token, depth);
+            if token.token().is_open_delim() {
+                depth += 1;
+            }
+        }
+    }
+
+    /// This function parses a list of elements separated by `sep`, running `elem` on each element.
+    /// It stops after the first element that isn't followed by `sep`, leaving the cursor on that token.
+    /// If the stream is already at its end, the list is empty. A trailing separator is an error, use
+    /// `TokenStream::parse_separated_trailing()` to allow one.
+    /// ```
+    /// use token_stream2::{ParseError, Token, TokenStream};
+    ///
+    /// fn ident(stream: &mut TokenStream) -> Result<String, ParseError> {
+    ///     match stream.peek_token(0).and_then(Token::as_ident).map(str::to_string) {
+    ///         Some(name) => {
+    ///             stream.next();
+    ///             Ok(name)
+    ///         }
+    ///         None => Err(ParseError::new(proc_macro2::Span::call_site(), "expected an identifier")),
+    ///     }
+    /// }
+    ///
+    /// let mut stream: TokenStream = "a, b, c; d".parse().unwrap();
+    /// let idents = stream.parse_separated(&Token::Comma, ident).unwrap();
+    /// assert!(idents == vec!["a", "b", "c"]);
+    /// assert!(stream.peek_token(0) == Some(&Token::Semi));
+    ///
+    /// let mut empty = TokenStream::new();
+    /// assert!(empty.parse_separated(&Token::Comma, ident).unwrap().is_empty());
+    ///
+    /// let mut trailing: TokenStream = "a, b,".parse().unwrap();
+    /// let err = trailing.parse_separated(&Token::Comma, ident).unwrap_err();
+    /// assert!(err.message() == "expected an element after `,`, found end of input");
+    /// ```
+    pub fn parse_separated<T>(
+        &mut self,
+        sep: &Token,
+        elem: impl FnMut(&mut TokenStream) -> Result<T, ParseError>,
+    ) -> Result<Vec<T>, ParseError> {
+        self.separated(sep, elem, false)
+    }
+
+    /// This function works like `TokenStream::parse_separated()`, but also accepts a separator after the last element.
+    /// ```
+    /// use token_stream2::{ParseError, Token, TokenStream};
+    ///
+    /// fn integer(stream: &mut TokenStream) -> Result<i128, ParseError> {
+    ///     stream
+    ///         .next()
+    ///         .and_then(|token| token.token().as_integer())
+    ///         .ok_or_else(|| ParseError::new(proc_macro2::Span::call_site(), "expected an integer"))
+    /// }
+    ///
+    /// let mut stream: TokenStream = "1, 2, 3,".parse().unwrap();
+    /// assert!(stream.parse_separated_trailing(&Token::Comma, integer).unwrap() == vec![1, 2, 3]);
+    /// assert!(stream.next().is_none());
+    /// ```
+    pub fn parse_separated_trailing<T>(
+        &mut self,
+        sep: &Token,
+        elem: impl FnMut(&mut TokenStream) -> Result<T, ParseError>,
+    ) -> Result<Vec<T>, ParseError> {
+        self.separated(sep, elem, true)
+    }
+
+    // This function implements `parse_separated` and `parse_separated_trailing`.
+    fn separated<T>(
+        &mut self,
+        sep: &Token,
+        mut elem: impl FnMut(&mut TokenStream) -> Result<T, ParseError>,
+        allow_trailing: bool,
+    ) -> Result<Vec<T>, ParseError> {
+        let mut elements = vec![];
+        if self.current().is_none() {
+            return Ok(elements);
+        }
+        loop {
+            elements.push(elem(self)?);
+            if self.consume_if(sep).is_none() {
+                return Ok(elements);
+            }
+            if self.current().is_none() {
+                if allow_trailing {
+                    return Ok(elements);
+                }
+                return Err(self.unexpected(format!("expected an element after `{}`", sep)));
+            }
+        }
+    }
+
+    /// This function collects the tokens inside the group that the cursor is on, correctly handling nested groups.
+    /// The cursor must be on an opening delimiter, and is left just past its matching closing delimiter.
+    /// If the group is mismatched or never closed, an error is returned and the cursor doesn't move.
+    /// ```
+    /// use token_stream2::Token;
+    ///
+    /// let mut stream: token_stream2::TokenStream = "(a (b) { c }) d".parse().unwrap();
+    /// let inner = stream.collect_group().unwrap();
+    /// assert!(inner.len() == 7);
+    /// assert!(inner[0].token() == &Token::Ident("a".into()));
+    /// assert!(inner[6].token() == &Token::CloseBrace);
+    /// assert!(stream.next().unwrap().token() == &Token::Ident("d".into()));
+    /// assert!(stream.collect_group().is_err());
+    /// ```
+    pub fn collect_group(&mut self) -> Result<Vec<SpannedToken>, ParseError> {
+        let start = self.iter_ptr;
+        let end = self.end();
+        let group_end = self.group_end(start).and_then(|group_end| {
+            if group_end < end {
+                Ok(group_end)
+            } else {
+                // The closing delimiter was already taken from the back of the stream.
+                Err(GroupError::Unclosed)
+            }
+        });
+        match group_end {
+            Ok(end) => {
+                self.iter_ptr = end + 1;
+                Ok(self.tokens[start + 1..end].to_vec())
+            }
+            Err(GroupError::NotAnOpener) => {
+                Err(self.unexpected("expected an opening delimiter".to_string()))
+            }
+            Err(GroupError::Mismatched(index)) => {
+                let token = &self.tokens[index];
+                Err(ParseError::new(
+                    token.span,
+                    format!("mismatched closing delimiter `{}`", token.token),
+                )
+                .with_found(token.token.clone()))
+            }
+            Err(GroupError::Unclosed) => Err(ParseError::new(
+                self.tokens[start].span,
+                format!("unclosed delimiter `{}`", self.tokens[start].token),
+            )),
+        }
+    }
+
+    /// This function parses a group that starts with `open` and ends with `close`, running `inner` on the tokens between them.
+    /// `inner` gets a stream of its own, which it has to consume completely.
+    /// On success the cursor is left just past `close`. On failure, including failures of `inner`, the cursor doesn't move.
+    /// ```
+    /// use token_stream2::{ParseError, Token, TokenStream};
+    ///
+    /// let mut stream: TokenStream = "(a, [b]) c".parse().unwrap();
+    /// let inner = stream
+    ///     .delimited(
+    ///         &Token::OpenParen,
+    ///         |inner| {
+    ///             let first = inner.expect(&Token::Ident("a".into()))?;
+    ///             inner.expect(&Token::Comma)?;
+    ///             let second = inner.delimited(&Token::OpenBracket, |inner| inner.expect(&Token::Ident("b".into())), &Token::CloseBracket)?;
+    ///             Ok((first, second))
+    ///         },
+    ///         &Token::CloseParen,
+    ///     )
+    ///     .unwrap();
+    /// assert!(inner.1.token() == &Token::Ident("b".into()));
+    /// assert!(stream.next().unwrap().token() == &Token::Ident("c".into()));
+    ///
+    /// let mut stream: TokenStream = "(a b)".parse().unwrap();
+    /// let err = stream
+    ///     .delimited(&Token::OpenParen, |inner| inner.expect(&Token::Ident("a".into())), &Token::CloseParen)
+    ///     .unwrap_err();
+    /// assert!(err.message() == "expected `)`, found `b`");
+    /// assert!(stream.position() == 0);
+    /// ```
+    pub fn delimited<T>(
+        &mut self,
+        open: &Token,
+        inner: impl FnOnce(&mut TokenStream) -> Result<T, ParseError>,
+        close: &Token,
+    ) -> Result<T, ParseError> {
+        if self.current().map(SpannedToken::token) != Some(open) {
+            return Err(self
+                .unexpected(format!("expected `{}`", open))
+                .with_expected(open.clone()));
+        }
+        let checkpoint = self.checkpoint();
+        let mut group = TokenStream::with_tokens(self.collect_group()?);
+        let closing = &self.tokens[self.iter_ptr - 1];
+        let result = if closing.token() != close {
+            Err(ParseError::new(
+                closing.span,
+                format!("expected `{}`, found `{}`", close, closing.token),
+            )
+            .with_expected(close.clone())
+            .with_found(closing.token.clone()))
+        } else {
+            inner(&mut group).and_then(|value| match group.current() {
+                Some(_) => Err(group
+                    .unexpected(format!("expected `{}`", close))
+                    .with_expected(close.clone())),
+                None => Ok(value),
+            })
+        };
+        if result.is_err() {
+            self.restore(checkpoint);
+        }
+        result
+    }
+
+    /// This function finds the index of the closing delimiter that matches the opening delimiter at `open_index`.
+    /// It returns `None` if `open_index` isn't an opening delimiter, or if the group is mismatched or never closed.
+    /// ```
+    /// let stream: token_stream2::TokenStream = "(a [b] c) {}".parse().unwrap();
+    /// assert!(stream.matching_delimiter(0) == Some(6));
+    /// assert!(stream.matching_delimiter(2) == Some(4));
+    /// assert!(stream.matching_delimiter(1) == None);
+    /// assert!(stream.matching_delimiter(7) == Some(8));
+    /// assert!(stream.matching_delimiter(9) == None);
+    ///
+    /// use token_stream2::Token;
+    /// let unclosed: token_stream2::TokenStream = vec![Token::OpenBrace, Token::OpenParen, Token::CloseParen].into_iter().collect();
+    /// assert!(unclosed.matching_delimiter(0) == None);
+    /// assert!(unclosed.matching_delimiter(1) == Some(2));
+    /// ```
+    pub fn matching_delimiter(&self, open_index: usize) -> Option<usize> {
+        self.group_end(open_index).ok()
+    }
+
+    /// This function returns whether every opening delimiter in the stream is closed by a closing delimiter of the same kind, in order.
+    /// Streams converted from `proc_macro2` are always balanced.
+    /// ```
+    /// let stream: token_stream2::TokenStream = "fn main() { let x = [1, (2)]; }".parse().unwrap();
+    /// assert!(stream.is_balanced());
+    ///
+    /// use token_stream2::Token;
+    /// let mismatched: token_stream2::TokenStream = vec![Token::OpenParen, Token::CloseBrace].into_iter().collect();
+    /// assert!(!mismatched.is_balanced());
+    /// let unclosed: token_stream2::TokenStream = vec![Token::OpenBracket].into_iter().collect();
+    /// assert!(!unclosed.is_balanced());
+    /// ```
+    pub fn is_balanced(&self) -> bool {
+        let mut open_groups = vec![];
+        for token in self.tokens.iter() {
+            if let Some(delimiter) = open_delimiter(&token.token) {
+                open_groups.push(delimiter);
+            } else if let Some(delimiter) = close_delimiter(&token.token) {
+                if open_groups.pop() != Some(delimiter) {
+                    return false;
+                }
+            }
+        }
+        open_groups.is_empty()
+    }
+
+    // This function scans forward from the opening delimiter at `open_index` to the closing delimiter that matches it.
+    fn group_end(&self, open_index: usize) -> Result<usize, GroupError> {
+        match self.tokens.get(open_index) {
+            Some(token) if open_delimiter(&token.token).is_some() => {}
+            _ => return Err(GroupError::NotAnOpener),
+        }
+        let mut open_groups = vec![];
+        for (index, token) in self.tokens.iter().enumerate().skip(open_index) {
+            if let Some(delimiter) = open_delimiter(&token.token) {
+                open_groups.push(delimiter);
+            } else if let Some(delimiter) = close_delimiter(&token.token) {
+                if open_groups.pop() != Some(delimiter) {
+                    return Err(GroupError::Mismatched(index));
+                }
+                if open_groups.is_empty() {
+                    return Ok(index);
+                }
+            }
+        }
+        Err(GroupError::Unclosed)
+    }
+
+    // This function builds a `ParseError` pointing at the token under the cursor, or at the last token if the stream has ended.
+    fn unexpected(&self, message: String) -> ParseError {
+        match self.current() {
+            Some(found) => {
+                ParseError::new(found.span, format!("{}, found `{}`", message, found.token))
+                    .with_found(found.token.clone())
+            }
+            None => ParseError::new(
+                self.tokens
+                    .last()
+                    .map_or_else(proc_macro2::Span::call_site, |last| last.span),
+                format!("{}, found end of input", message),
+            ),
+        }
+    }
+}
+impl std::ops::Index<usize> for TokenStream {
+    type Output = SpannedToken;
+
+    /// This function returns the token at `index`, like `TokenStream::get()`.
+    ///
+    /// # Panics
+    /// This function panics if `index` is out of bounds, like indexing a slice.
+    /// ```
+    /// let stream: token_stream2::TokenStream = "a(b)".parse().unwrap();
+    /// assert!(stream[1].token() == &token_stream2::Token::OpenParen);
+    /// ```
+    /// ```should_panic
+    /// let stream: token_stream2::TokenStream = "a(b)".parse().unwrap();
+    /// let _ = &stream[4];
+    /// ```
+    fn index(&self, index: usize) -> &SpannedToken {
+        &self.tokens[index]
+    }
+}
+
+impl From<proc_macro2::TokenStream> for TokenStream {
+    /// This function converts a `proc_macro2::TokenStream` into a `token_stream2::TokenStream`.
+    /// Every literal produces exactly one token.
+    ///
+    /// # Panics
+    /// This function panics if the conversion fails. Use `TokenStream::try_convert` to handle the `ConvertError` instead.
+    /// ```
+    /// let to_parse: proc_macro2::TokenStream = "42".parse().expect("infallible");
+    ///
+    /// let stream: token_stream2::TokenStream = to_parse.into();
+    /// let tokens: Vec<token_stream2::SpannedToken> = stream.collect();
+    /// assert!(tokens.len() == 1);
+    /// assert!(tokens[0].token() == &token_stream2::Token::Integer(42));
+    /// ```
+    fn from(value: proc_macro2::TokenStream) -> Self {
+        match TokenStream::try_convert(value) {
+            Ok(stream) => stream,
+            Err(err) => panic!("failed to convert token stream: {}", err),
+        }
+    }
+}
+
+#[cfg(feature = "proc-macro")]
+impl From<proc_macro::TokenStream> for TokenStream {
+    /// This function converts the compiler's `proc_macro::TokenStream` into a `token_stream2::TokenStream`, going through `proc_macro2`.
+    /// It panics if the conversion fails, like `From<proc_macro2::TokenStream>`.
+    /// This implementation requires the `proc-macro` feature, and like everything in `proc_macro`, it only works inside a procedural macro.
+    /// ```no_run
+    /// extern crate proc_macro;
+    ///
+    /// fn my_macro(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    ///     let stream = token_stream2::TokenStream::from(input);
+    ///     # let _ = stream;
+    ///     proc_macro::TokenStream::new()
+    /// }
+    /// ```
+    fn from(value: proc_macro::TokenStream) -> Self {
+        proc_macro2::TokenStream::from(value).into()
+    }
+}
+
+impl std::str::FromStr for TokenStream {
+    type Err = ConvertError;
+
+    /// This function tokenizes source text directly into a `token_stream2::TokenStream`.
+    /// A shebang line at the very start is skipped, see `TokenStream::shebang()`.
+    /// ```
+    /// let stream: token_stream2::TokenStream = "fn main() {}".parse().unwrap();
+    /// assert!(stream.peek(0).unwrap().token() == &token_stream2::Token::Ident("fn".into()));
+    ///
+    /// let err = "(".parse::<token_stream2::TokenStream>().unwrap_err();
+    /// assert!(matches!(err, token_stream2::ConvertError::LexError { .. }));
+    /// ```
+    fn from_str(source: &str) -> Result<Self, ConvertError> {
+        let shebang = source
+            .strip_prefix("#!")
+            .filter(|rest| !skip_trivia(rest).starts_with('['))
+            .map(|_| source.lines().next().unwrap_or(source));
+        // The shebang is blanked out rather than removed, so the spans of the other tokens don't move.
+        let blanked;
+        let source = match shebang {
+            Some(line) => {
+                blanked = format!("{}{}", " ".repeat(line.len()), &source[line.len()..]);
+                &blanked
+            }
+            None => source,
+        };
+        match source.parse::<proc_macro2::TokenStream>() {
+            Ok(tokens) => TokenStream::try_convert(tokens).map(|mut stream| {
+                stream.shebang = shebang.map(str::to_string);
+                stream
+            }),
+            Err(err) => Err(ConvertError::LexError {
+                message: err.to_string(),
+                span: err.span(),
+            }),
+        }
+    }
+}
+
+impl FromIterator<SpannedToken> for TokenStream {
+    /// This function builds a stream out of tokens that already have spans, with the cursor at the start.
+    /// ```
+    /// use token_stream2::{SpannedToken, Token, TokenStream};
+    ///
+    /// let stream: TokenStream = "let x = 1;".parse().unwrap();
+    /// let idents: TokenStream = stream.filter(|token| token.token().is_ident()).collect();
+    /// let tokens: Vec<Token> = idents.map(|token| token.token().clone()).collect();
+    /// assert!(tokens == vec![Token::Ident("let".into()), Token::Ident("x".into())]);
+    /// ```
+    fn from_iter<I: IntoIterator<Item = SpannedToken>>(iter: I) -> Self {
+        TokenStream::with_tokens(iter.into_iter().collect())
+    }
+}
+
+impl FromIterator<Token> for TokenStream {
+    /// This function builds a stream out of bare tokens, with the cursor at the start.
+    /// Every token gets `Span::call_site()` as its span.
+    /// ```
+    /// use token_stream2::{Token, TokenStream};
+    ///
+    /// let tokens = vec![Token::OpenParen, Token::Ident("a".into()), Token::CloseBracket];
+    /// let stream: TokenStream = tokens.clone().into_iter().collect();
+    /// assert!(!stream.is_balanced());
+    /// assert!(stream.matching_delimiter(0) == None);
+    ///
+    /// let round_trip: Vec<Token> = stream.map(|token| token.token().clone()).collect();
+    /// assert!(round_trip == tokens);
+    /// ```
+    fn from_iter<I: IntoIterator<Item = Token>>(iter: I) -> Self {
+        iter.into_iter()
+            .map(|token| SpannedToken::new(token, proc_macro2::Span::call_site()))
+            .collect()
+    }
+}
+
+impl Extend<SpannedToken> for TokenStream {
+    /// This function appends tokens to the stream, where the cursor will reach them after the existing tokens.
+    /// If tokens were taken from the back with `next_back()`, the new tokens go in front of them,
+    /// so they are still visible to `next()` and `peek()`.
+    /// ```
+    /// use token_stream2::{Token, TokenStream};
+    ///
+    /// let mut stream: TokenStream = "a b".parse().unwrap();
+    /// assert!(stream.next().unwrap().token() == &Token::Ident("a".into()));
+    /// let more: TokenStream = "c d".parse().unwrap();
+    /// stream.extend(more);
+    /// assert!(stream.peek(2).unwrap().token() == &Token::Ident("d".into()));
+    ///
+    /// let rest: Vec<Token> = stream.map(|token| token.token().clone()).collect();
+    /// assert!(rest == vec![Token::Ident("b".into()), Token::Ident("c".into()), Token::Ident("d".into())]);
+    /// ```
+    fn extend<I: IntoIterator<Item = SpannedToken>>(&mut self, iter: I) {
+        let end = self.end();
+        std::rc::Rc::make_mut(&mut self.tokens).splice(end..end, iter);
+    }
+}
+
+impl Extend<Token> for TokenStream {
+    /// This function appends bare tokens to the stream, like `Extend<SpannedToken>`.
+    /// Every token gets `Span::call_site()` as its span.
+    /// ```
+    /// use token_stream2::{Token, TokenStream};
+    ///
+    /// let mut stream: TokenStream = "x".parse().unwrap();
+    /// stream.extend(vec![Token::Semi]);
+    /// assert!(stream.to_string() == "x;\n");
+    /// ```
+    fn extend<I: IntoIterator<Item = Token>>(&mut self, iter: I) {
+        self.extend(
+            iter.into_iter()
+                .map(|token| SpannedToken::new(token, proc_macro2::Span::call_site())),
+        );
+    }
+}
+
+impl<'a> IntoIterator for &'a TokenStream {
+    type Item = &'a SpannedToken;
+    type IntoIter = std::slice::Iter<'a, SpannedToken>;
+
+    /// This function iterates over every token in the stream by reference, like `TokenStream::iter()`.
+    /// The cursor isn't moved, so the stream can be looped over any number of times.
+    /// ```
+    /// let stream: token_stream2::TokenStream = "a + b".parse().unwrap();
+    /// let mut count = 0;
+    /// for token in &stream {
+    ///     count += token.token().is_ident() as usize;
+    /// }
+    /// for token in &stream {
+    ///     count += token.token().is_ident() as usize;
+    /// }
+    /// assert!(count == 4);
+    /// assert!(stream.position() == 0);
+    /// ```
+    fn into_iter(self) -> Self::IntoIter {
+        self.tokens.iter()
+    }
+}
+
+impl TryFrom<TokenStream> for proc_macro2::TokenStream {
+    type Error = ConvertError;
+
+    /// This function converts a `token_stream2::TokenStream` back into a `proc_macro2::TokenStream`, re-nesting the delimiter tokens into groups.
+    /// Every token is converted, including ones the cursor has already moved past.
+    /// This fails if the delimiters aren't balanced, or if a token has no `proc_macro2` equivalent.
+    /// ```
+    /// let to_parse: proc_macro2::TokenStream = r#"
+    ///      fn main() -> u8 {
+    ///          println!("{}", 'c');
+    ///          'outer: loop { break 'outer 0x10u8; }
+    ///      }
+    ///  "#
+    ///  .parse()
+    ///  .expect("infallible");
+    ///
+    /// let stream: token_stream2::TokenStream = to_parse.into();
+    /// let kinds: Vec<token_stream2::Token> = stream.clone().map(|t| t.token().clone()).collect();
+    /// let round_trip: proc_macro2::TokenStream = stream.try_into().unwrap();
+    /// let stream: token_stream2::TokenStream = round_trip.into();
+    /// assert!(stream.map(|t| t.token().clone()).collect::<Vec<_>>() == kinds);
+    /// ```
+    /// Unbalanced delimiters are reported with the closing delimiter that was needed.
+    /// ```
+    /// use token_stream2::{ConvertError, Token, TokenStream};
+    ///
+    /// let convert = |tokens: Vec<Token>| {
+    ///     let stream: TokenStream = tokens.into_iter().collect();
+    ///     proc_macro2::TokenStream::try_from(stream).unwrap_err()
+    /// };
+    ///
+    /// let stray = convert(vec![Token::Ident("a".into()), Token::CloseBrace]);
+    /// assert!(matches!(&stray, ConvertError::UnexpectedDelimiter { index: 1, expected: None, .. }));
+    /// assert!(stray.to_string() == "unexpected `}` at index 1, no group is open");
+    ///
+    /// let mismatched = convert(vec![Token::OpenParen, Token::CloseBracket]);
+    /// assert!(matches!(&mismatched, ConvertError::UnexpectedDelimiter { expected: Some(Token::CloseParen), .. }));
+    /// assert!(mismatched.to_string() == "unexpected `]` at index 1, expected `)`");
+    ///
+    /// let unclosed = convert(vec![Token::OpenBrace, Token::Ident("a".into())]);
+    /// assert!(matches!(&unclosed, ConvertError::UnclosedDelimiter { index: 0, expected: Token::CloseBrace, .. }));
+    /// assert!(unclosed.to_string() == "unclosed `{` at index 0, expected `}`");
+    /// ```
+    fn try_from(value: TokenStream) -> Result<Self, ConvertError> {
+        let mut open_groups: Vec<(usize, &SpannedToken, Vec<proc_macro2::TokenTree>)> = vec![];
+        let mut trees = vec![];
+        for (index, token) in value.tokens.iter().enumerate() {
+            if open_delimiter(&token.token).is_some() {
+                open_groups.push((index, token, std::mem::take(&mut trees)));
+            } else if let Some(delimiter) = close_delimiter(&token.token) {
+                match open_groups.pop() {
+                    Some((_, open, parent)) if open_delimiter(&open.token) == Some(delimiter) => {
+                        let mut group = proc_macro2::Group::new(
+                            delimiter,
+                            std::mem::replace(&mut trees, parent).into_iter().collect(),
+                        );
+                        group.set_span(open.span);
+                        trees.push(proc_macro2::TokenTree::Group(group));
+                    }
+                    open => {
+                        return Err(ConvertError::UnexpectedDelimiter {
+                            index,
+                            found: token.token.clone(),
+                            expected: open.and_then(|(_, open, _)| {
+                                open_delimiter(&open.token).map(close_token)
+                            }),
+                            span: token.span,
+                        })
+                    }
+                }
+            } else {
+                match token_to_trees(token) {
+                    Some(converted) => trees.extend(converted),
+                    None => {
+                        return Err(ConvertError::InvalidToken {
+                            index,
+                            token: token.token.clone(),
+                            span: token.span,
+                        })
+                    }
+                }
+            }
+        }
+        if let Some((index, open, _)) = open_groups.pop() {
+            return Err(ConvertError::UnclosedDelimiter {
+                index,
+                open: open.token.clone(),
+                expected: open_delimiter(&open.token).map_or(Token::None, close_token),
+                span: open.span,
+            });
+        }
+        Ok(trees.into_iter().collect())
+    }
+}
+
+/// This struct converts a `proc_macro2::TokenStream` into `SpannedToken`s one at a time, as they are needed.
+/// It produces exactly the same tokens as `TokenStream::try_convert()`, which uses it internally,
+/// but a parser that only needs the start of a large input doesn't pay for converting the rest of it.
+///
+/// Each item is a `Result`, since a token might fail to convert. After an error, the iterator ends.
+/// No input from `proc_macro2` fails to convert today, see `ConvertError::InvalidLiteral`.
+/// ```
+/// use token_stream2::{ConvertError, LazyTokenStream, Token};
+///
+/// let to_parse: proc_macro2::TokenStream = r"struct Foo; b'\n'".parse().expect("infallible");
+/// let tail = to_parse.clone().into_iter().last().unwrap();
+///
+/// let mut lazy = LazyTokenStream::new(to_parse);
+/// let prefix: Vec<Token> = lazy.by_ref().take(3).map(|t| t.unwrap().token().clone()).collect();
+/// assert!(prefix == vec![Token::Ident("struct".into()), Token::Ident("Foo".into()), Token::Semi]);
+/// // The literal at the end is still an unconverted `proc_macro2` token.
+/// assert!(format!("{:?}", lazy).contains(&format!("{:?}", tail)));
+///
+/// let rest: Result<Vec<_>, ConvertError> = lazy.collect();
+/// assert!(rest.unwrap()[0].token() == &Token::ByteChar(b'\n'));
+/// ```
+#[derive(Clone, Debug)]
+pub struct LazyTokenStream {
+    // Instead of recursing into groups, this keeps a stack of the groups it is inside of, so deeply nested input can't overflow the call stack.
+    // Each entry holds the remaining tokens of a group, and the group itself so its closing delimiter can be produced once they run out.
+    stack: Vec<(
+        std::iter::Peekable<proc_macro2::token_stream::IntoIter>,
+        Option<proc_macro2::Group>,
+    )>,
+    options: ConvertOptions,
+    // This is whether a sign produced next would start an operand, for `ConvertOptions::signed_numbers()`.
+    operand_position: bool,
+}
+
+impl LazyTokenStream {
+    /// This function creates a `LazyTokenStream` with every option disabled, like the `From` implementation of `TokenStream`.
+    pub fn new(tokens: proc_macro2::TokenStream) -> Self {
+        LazyTokenStream::with_options(tokens, ConvertOptions::default())
+    }
+
+    /// This function creates a `LazyTokenStream` with `options` controlling which optional tokens are produced.
+    /// See `TokenStream::from_with_options()`.
+    pub fn with_options(tokens: proc_macro2::TokenStream, options: ConvertOptions) -> Self {
+        LazyTokenStream {
+            stack: vec![(tokens.into_iter().peekable(), None)],
+            options,
+            operand_position: true,
+        }
+    }
+
+    // This function folds a sign into the number literal that directly follows it in the same group, like `TokenStream::fold_signed_numbers()`.
+    fn fold_sign(&mut self, sign: SpannedToken) -> SpannedToken {
+        let negate = match sign.token {
+            Token::Minus => true,
+            Token::Plus => false,
+            _ => return sign,
+        };
+        if !self.operand_position {
+            return sign;
+        }
+        let folded = match self.stack.last_mut().and_then(|(tokens, _)| tokens.peek()) {
+            Some(proc_macro2::TokenTree::Literal(literal)) => {
+                convert_literal_tree(literal, self.options.float_text)
+                    .and_then(|number| signed_number(&number, negate))
+                    .map(|number| (number, literal.span()))
+            }
+            _ => None,
+        };
+        match folded {
+            Some((number, span)) => {
+                if let Some((tokens, _)) = self.stack.last_mut() {
+                    tokens.next();
+                }
+                SpannedToken {
+                    token: number,
+                    span: sign.span.join(span).unwrap_or(sign.span),
+                    spacing: None,
+                }
+            }
+            None => sign,
+        }
+    }
+
+    // This function converts the next token, without folding signs.
+    fn next_unfolded(&mut self) -> Option<Result<SpannedToken, ConvertError>> {
+        let (tokens, _) = self.stack.last_mut()?;
+        let token = match tokens.next() {
+            Some(token) => token,
+            None => {
+                return match self.stack.pop() {
+                    Some((_, Some(group))) => Some(Ok(SpannedToken {
+                        token: close_token(group.delimiter()),
+                        span: group.span(),
+                        spacing: None,
+                    })),
+                    _ => None,
+                };
+            }
+        };
+        let token = match token {
+            proc_macro2::TokenTree::Group(group) => {
+                let open = SpannedToken {
+                    token: match group.delimiter() {
+                        proc_macro2::Delimiter::Parenthesis => Token::OpenParen,
+                        proc_macro2::Delimiter::Brace => Token::OpenBrace,
+                        proc_macro2::Delimiter::Bracket => Token::OpenBracket,
+                        proc_macro2::Delimiter::None => Token::OpenNone,
+                    },
+                    span: group.span(),
+                    spacing: None,
+                };
+                self.stack
+                    .push((group.stream().into_iter().peekable(), Some(group)));
+                open
+            }
+            proc_macro2::TokenTree::Ident(ident) => {
+                let name = ident.to_string();
+                SpannedToken {
+                    token: match name.strip_prefix("r#") {
+                        Some(raw) => Token::RawIdent(raw.to_string()),
+                        None if self.options.bool_literals && name == "true" => Token::Bool(true),
+                        None if self.options.bool_literals && name == "false" => Token::Bool(false),
+                        None if self.options.underscore && name == "_" => Token::Underscore,
+                        None => Token::Ident(name),
+                    },
+                    span: ident.span(),
+                    spacing: None,
+                }
+            }
+            proc_macro2::TokenTree::Punct(punct) => {
+                if punct.as_char() == '\'' && punct.spacing() == proc_macro2::Spacing::Joint {
+                    if let Some(proc_macro2::TokenTree::Ident(ident)) = tokens.peek() {
+                        let name = ident.to_string();
+                        let span = punct.span().join(ident.span()).unwrap_or(punct.span());
+                        tokens.next();
+                        return Some(Ok(SpannedToken {
+                            token: Token::Lifetime(name),
+                            span,
+                            spacing: None,
+                        }));
+                    }
+                }
+                if punct.spacing() == proc_macro2::Spacing::Joint {
+                    if let Some(proc_macro2::TokenTree::Punct(next)) = tokens.peek() {
+                        if let Some(tok) = glue_puncts(punct.as_char(), next.as_char()) {
+                            let span = punct.span().join(next.span()).unwrap_or(punct.span());
+                            let spacing = next.spacing();
+                            tokens.next();
+                            return Some(Ok(SpannedToken {
+                                token: tok,
+                                span,
+                                spacing: Some(spacing),
+                            }));
+                        }
+                    }
+                }
+                let tok = match PUNCTS.iter().find(|(ch, _)| *ch == punct.as_char()) {
+                    Some((_, tok)) => tok.clone(),
+                    None => Token::UnknownPunct(punct.as_char()),
+                };
+                SpannedToken {
+                    token: tok,
+                    span: punct.span(),
+                    spacing: Some(punct.spacing()),
+                }
+            }
+            proc_macro2::TokenTree::Literal(literal) => {
+                match convert_literal_tree(&literal, self.options.float_text) {
+                    Some(tok) => SpannedToken {
+                        token: tok,
+                        span: literal.span(),
+                        spacing: None,
+                    },
+                    None => {
+                        // Nothing after a failed token is converted.
+                        self.stack.clear();
+                        return Some(Err(ConvertError::InvalidLiteral {
+                            literal: literal.to_string(),
+                            span: literal.span(),
+                        }));
+                    }
+                }
+            }
+        };
+        debug_assert!(
+            token.token != Token::None,
+            "the converter must never produce `Token::None`"
+        );
+        Some(Ok(token))
+    }
+}
+
+impl Iterator for LazyTokenStream {
+    type Item = Result<SpannedToken, ConvertError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut token = match self.next_unfolded()? {
+            Ok(token) => token,
+            Err(err) => return Some(Err(err)),
+        };
+        if self.options.signed_numbers {
+            token = self.fold_sign(token);
+            self.operand_position = starts_operand(&token.token);
+        }
+        Some(Ok(token))
+    }
+}
+
+// This function converts a literal token tree, producing `Token::FloatLit` for floats when `float_text` is set.
+fn convert_literal_tree(literal: &proc_macro2::Literal, float_text: bool) -> Option<Token> {
+    let str_value = literal.to_string();
+    match convert_literal(&str_value) {
+        Some(Token::Float(value)) | Some(Token::FloatSuffixed { value, .. }) if float_text => {
+            Some(Token::FloatLit {
+                value,
+                repr: str_value,
+            })
+        }
+        tok => tok,
+    }
+}
+
+// This function skips the whitespace and comments at the start of `text`, the way `rustc` does when looking for a shebang.
+// Doc comments are tokens rather than comments, so they aren't skipped.
+fn skip_trivia(mut text: &str) -> &str {
+    loop {
+        text = text.trim_start();
+        let is_doc = |rest: &str, doc: &str, not_doc: &str| {
+            rest.starts_with(doc) && !rest.starts_with(not_doc)
+        };
+        if text.starts_with("//") && !text.starts_with("//!") && !is_doc(text, "///", "////") {
+            text = text.find('\n').map_or("", |end| &text[end..]);
+        } else if text.starts_with("/*")
+            && !text.starts_with("/*!")
+            && (!is_doc(text, "/**", "/***") || text.starts_with("/**/"))
+        {
+            // Block comments nest, so the comment only ends once every `/*` inside it is closed.
+            let mut depth = 0usize;
+            let mut rest = text;
+            loop {
+                if rest.starts_with("/*") {
+                    depth += 1;
+                    rest = &rest[2..];
+                } else if rest.starts_with("*/") {
+                    depth -= 1;
+                    rest = &rest[2..];
+                    if depth == 0 {
+                        break;
+                    }
+                } else if let Some(ch) = rest.chars().next() {
+                    rest = &rest[ch.len_utf8()..];
+                } else {
+                    // An unterminated comment is left for the lexer to report.
+                    return text;
+                }
+            }
+            text = rest;
+        } else {
+            return text;
+        }
+    }
+}
+
+// This function returns whether a sign right after `previous` starts an operand, rather than being a binary operator.
+fn starts_operand(previous: &Token) -> bool {
+    // A postfix `?` ends an operand, so a sign after it is a binary operator.
+    (previous.is_punct() && previous != &Token::Question)
+        || previous.is_open_delim()
+        || matches!(
+            previous.as_keyword(),
+            Some(
+                Keyword::Become
+                    | Keyword::Break
+                    | Keyword::Else
+                    | Keyword::If
+                    | Keyword::In
+                    | Keyword::Let
+                    | Keyword::Match
+                    | Keyword::Return
+                    | Keyword::While
+                    | Keyword::Yield
+            )
+        )
+}
+
+// This function applies a sign to a number token for `TokenStream::fold_signed_numbers`, if it is a number that can be signed.
+fn signed_number(token: &Token, negate: bool) -> Option<Token> {
+    let tok = match token {
+        Token::Integer(value) if negate => Token::Integer(value.checked_neg()?),
+        // The magnitude of `i128::MIN` doesn't fit in an `i128`, so it is only an integer once it is negated.
+        Token::BigInteger(digits) if negate => Token::Integer(parse_negated_integer(digits)?),
+        Token::BigIntegerSuffixed { digits, suffix } if negate && &**suffix == "i128" => {
+            Token::IntegerSuffixed {
+                value: parse_negated_integer(digits)?,
+                suffix: suffix.to_string(),
+            }
+        }
+        Token::Float(value) if negate => Token::Float(-value),
+        Token::IntegerSuffixed { value, suffix } if !suffix.starts_with('u') => {
+            Token::IntegerSuffixed {
+                value: if negate { value.checked_neg()? } else { *value },
+                suffix: suffix.clone(),
+            }
+        }
+        Token::FloatSuffixed { value, suffix } => Token::FloatSuffixed {
+            value: if negate { -value } else { *value },
+            suffix: suffix.clone(),
+        },
+        Token::FloatLit { value, repr } => Token::FloatLit {
+            value: if negate { -value } else { *value },
+            repr: if negate {
+                format!("-{}", repr)
+            } else {
+                repr.clone()
+            },
+        },
+        Token::Integer(..) | Token::Float(..) => token.clone(),
+        _ => return None,
+    };
+    Some(tok)
+}
+
+// This enum represents the reasons `TokenStream::group_end` can fail to find the end of a group.
+enum GroupError {
+    NotAnOpener,
+    Mismatched(usize),
+    Unclosed,
+}
+
+// This function returns the kind of group that `token` opens, if it opens one.
+fn open_delimiter(token: &Token) -> Option<proc_macro2::Delimiter> {
+    match token {
+        Token::OpenParen => Some(proc_macro2::Delimiter::Parenthesis),
+        Token::OpenBrace => Some(proc_macro2::Delimiter::Brace),
+        Token::OpenBracket => Some(proc_macro2::Delimiter::Bracket),
+        Token::OpenNone => Some(proc_macro2::Delimiter::None),
+        _ => None,
+    }
+}
+
+// This function returns the token that closes a group of the given kind.
+fn close_token(delimiter: proc_macro2::Delimiter) -> Token {
+    match delimiter {
+        proc_macro2::Delimiter::Parenthesis => Token::CloseParen,
+        proc_macro2::Delimiter::Brace => Token::CloseBrace,
+        proc_macro2::Delimiter::Bracket => Token::CloseBracket,
+        proc_macro2::Delimiter::None => Token::CloseNone,
+    }
+}
+
+// This function returns the kind of group that `token` closes, if it closes one.
+fn close_delimiter(token: &Token) -> Option<proc_macro2::Delimiter> {
+    match token {
+        Token::CloseParen => Some(proc_macro2::Delimiter::Parenthesis),
+        Token::CloseBrace => Some(proc_macro2::Delimiter::Brace),
+        Token::CloseBracket => Some(proc_macro2::Delimiter::Bracket),
+        Token::CloseNone => Some(proc_macro2::Delimiter::None),
+        _ => None,
+    }
+}
+
+// This function returns the source spelling of a punctuation token, if it is one.
+fn punct_str(token: &Token) -> Option<&'static str> {
+    let text = match token {
+        Token::Plus => "+",
+        Token::Minus => "-",
+        Token::Slash => "/",
+        Token::Star => "*",
+        Token::At => "@",
+        Token::Ampersand => "&",
+        Token::Semi => ";",
+        Token::Colon => ":",
+        Token::GreaterThan => ">",
+        Token::LessThan => "<",
+        Token::Comma => ",",
+        Token::SingleQuote => "'",
+        Token::DoubleQuote => "\"",
+        Token::Bang => "!",
+        Token::Question => "?",
+        Token::Dot => ".",
+        Token::Tilde => "~",
+        Token::Percent => "%",
+        Token::Caret => "^",
+        Token::Pipe => "|",
+        Token::Hash => "#",
+        Token::Dollar => "$",
+        Token::Equal => "=",
+        Token::PathSep => "::",
+        Token::RArrow => "->",
+        Token::FatArrow => "=>",
+        Token::EqEq => "==",
+        Token::Ne => "!=",
+        Token::Le => "<=",
+        Token::Ge => ">=",
+        Token::AndAnd => "&&",
+        Token::OrOr => "||",
+        Token::Shl => "<<",
+        Token::Shr => ">>",
+        _ => return None,
+    };
+    Some(text)
+}
+
+// This function converts a single non-delimiter token into the `proc_macro2` trees it stands for.
+// It returns `None` if there is no equivalent, for example an identifier that isn't valid.
+fn token_to_trees(token: &SpannedToken) -> Option<Vec<proc_macro2::TokenTree>> {
+    let span = token.span;
+    let mut trees: Vec<proc_macro2::TokenTree> = match &token.token {
+        Token::Ident(name) => vec![parse_ident(name)?.into()],
+        Token::RawIdent(name) => vec![parse_ident(&format!("r#{}", name))?.into()],
+        Token::Bool(value) => vec![parse_ident(if *value { "true" } else { "false" })?.into()],
+        Token::Underscore => vec![parse_ident("_")?.into()],
+        Token::Lifetime(name) => vec![
+            proc_macro2::Punct::new('\'', proc_macro2::Spacing::Joint).into(),
+            parse_ident(name)?.into(),
+        ],
+        Token::Integer(value) => vec![proc_macro2::Literal::i128_unsuffixed(*value).into()],
+        Token::BigInteger(digits) => vec![parse_literal(digits)?.into()],
+        Token::BigIntegerSuffixed { digits, suffix } => {
+            vec![parse_literal(&format!("{}{}", digits, suffix))?.into()]
+        }
+        Token::Float(value) if value.is_finite() => {
+            vec![proc_macro2::Literal::f64_unsuffixed(*value).into()]
+        }
+        Token::Float(..) => return None,
+        Token::IntegerSuffixed { value, suffix } => {
+            vec![parse_literal(&format!("{}{}", value, suffix))?.into()]
+        }
+        Token::FloatSuffixed { value, suffix } => {
+            vec![parse_literal(&format!("{:?}{}", value, suffix))?.into()]
+        }
+        Token::FloatLit { repr, .. } => vec![parse_literal(repr)?.into()],
+        Token::Char(value) => vec![proc_macro2::Literal::character(*value).into()],
+        Token::ByteChar(value) => {
+            vec![parse_literal(&format!("b'{}'", std::ascii::escape_default(*value)))?.into()]
+        }
+        Token::String(content) => vec![parse_literal(&format!("\"{}\"", content))?.into()],
+        Token::ByteString(content) => vec![parse_literal(&format!("b\"{}\"", content))?.into()],
+        Token::RawString { content, hashes } => {
+            let hashes = "#".repeat(*hashes);
+            vec![parse_literal(&format!("r{}\"{}\"{}", hashes, content, hashes))?.into()]
+        }
+        Token::RawByteString { content, hashes } => {
+            let hashes = "#".repeat(*hashes);
+            vec![parse_literal(&format!("br{}\"{}\"{}", hashes, content, hashes))?.into()]
+        }
+        Token::Literal(text) => vec![parse_literal(text)?.into()],
+        Token::None => vec![],
+        // Every character `proc_macro2::Punct::new` accepts already has a token, so this one would make it panic.
+        Token::UnknownPunct(..) => return None,
+        other => {
+            // `"` is a token of its own here, but `proc_macro2` never treats it as punctuation.
+            let text = punct_str(other).filter(|text| *text != "\"")?;
+            let mut puncts = vec![];
+            for (i, ch) in text.char_indices() {
+                let spacing = if i + ch.len_utf8() < text.len() {
+                    proc_macro2::Spacing::Joint
+                } else {
+                    token.spacing.unwrap_or(proc_macro2::Spacing::Alone)
+                };
+                puncts.push(proc_macro2::Punct::new(ch, spacing).into());
+            }
+            puncts
+        }
+    };
+    for tree in &mut trees {
+        tree.set_span(span);
+    }
+    Some(trees)
+}
+
+// This function parses `name` as a single identifier, without panicking if it isn't one.
+fn parse_ident(name: &str) -> Option<proc_macro2::Ident> {
+    let mut trees = name.parse::<proc_macro2::TokenStream>().ok()?.into_iter();
+    match (trees.next(), trees.next()) {
+        (Some(proc_macro2::TokenTree::Ident(ident)), None) if ident == name => Some(ident),
+        _ => None,
+    }
+}
+
+// This function parses `text` as a single literal, without panicking if it isn't one.
+fn parse_literal(text: &str) -> Option<proc_macro2::Literal> {
+    text.parse::<proc_macro2::Literal>().ok()
+}
+
+// This table maps every single-character punctuation to its token, in both directions.
+const PUNCTS: [(char, Token); 23] = [
+    ('+', Token::Plus),
+    ('-', Token::Minus),
+    ('>', Token::GreaterThan),
+    ('<', Token::LessThan),
+    ('@', Token::At),
+    ('/', Token::Slash),
+    ('*', Token::Star),
+    ('&', Token::Ampersand),
+    (';', Token::Semi),
+    (':', Token::Colon),
+    ('"', Token::DoubleQuote),
+    ('\'', Token::SingleQuote),
+    ('?', Token::Question),
+    ('!', Token::Bang),
+    (',', Token::Comma),
+    ('.', Token::Dot),
+    ('~', Token::Tilde),
+    ('%', Token::Percent),
+    ('^', Token::Caret),
+    ('|', Token::Pipe),
+    ('#', Token::Hash),
+    ('$', Token::Dollar),
+    ('=', Token::Equal),
+];
+
+// This function combines two adjacent punctuation characters into a multi-character operator, if they form one.
+fn glue_puncts(first: char, second: char) -> Option<Token> {
+    let tok = match (first, second) {
+        (':', ':') => Token::PathSep,
+        ('-', '>') => Token::RArrow,
+        ('=', '>') => Token::FatArrow,
+        ('=', '=') => Token::EqEq,
+        ('!', '=') => Token::Ne,
+        ('<', '=') => Token::Le,
+        ('>', '=') => Token::Ge,
+        ('&', '&') => Token::AndAnd,
+        ('|', '|') => Token::OrOr,
+        ('<', '<') => Token::Shl,
+        ('>', '>') => Token::Shr,
+        _ => return None,
+    };
+    Some(tok)
+}
+
+const INTEGER_SUFFIXES: [&str; 12] = [
+    "u8", "u16", "u32", "u64", "u128", "usize", "i8", "i16", "i32", "i64", "i128", "isize",
+];
+const FLOAT_SUFFIXES: [&str; 2] = ["f32", "f64"];
+
+// This function parses an unsuffixed integer literal in normal, hexadecimal, binary, or octal form.
+fn parse_integer(number: &str) -> Option<i128> {
+    if let Some(hex) = number.strip_prefix("0x") {
+        i128::from_str_radix(&hex.replace('_', ""), 16).ok()
+    } else if let Some(binary) = number.strip_prefix("0b") {
+        i128::from_str_radix(&binary.replace('_', ""), 2).ok()
+    } else if let Some(octal) = number.strip_prefix("0o") {
+        i128::from_str_radix(&octal.replace('_', ""), 8).ok()
+    } else {
+        remove_separators(number)?.parse::<i128>().ok()
+    }
+}
+
+// This function parses the negation of an unsuffixed integer literal, which fits one more value than `parse_integer` can.
+fn parse_negated_integer(number: &str) -> Option<i128> {
+    let (digits, radix) = if let Some(hex) = number.strip_prefix("0x") {
+        (hex, 16)
+    } else if let Some(binary) = number.strip_prefix("0b") {
+        (binary, 2)
+    } else if let Some(octal) = number.strip_prefix("0o") {
+        (octal, 8)
+    } else {
+        (number, 10)
+    };
+    i128::from_str_radix(&format!("-{}", digits.replace('_', "")), radix).ok()
+}
+
+// This function returns whether `number` is a well-formed unsuffixed integer literal, regardless of its size.
+fn is_integer_literal(number: &str) -> bool {
+    let (digits, radix) = if let Some(hex) = number.strip_prefix("0x") {
+        (hex, 16)
+    } else if let Some(binary) = number.strip_prefix("0b") {
+        (binary, 2)
+    } else if let Some(octal) = number.strip_prefix("0o") {
+        (octal, 8)
+    } else if number.starts_with('_') {
+        return false;
+    } else {
+        (number, 10)
+    };
+    digits.chars().any(|ch| ch.is_digit(radix))
+        && digits.chars().all(|ch| ch == '_' || ch.is_digit(radix))
+}
+
+// This function parses an unsuffixed float literal.
+fn parse_float(number: &str) -> Option<f64> {
+    remove_separators(number)?.parse::<f64>().ok()
+}
+
+// This function removes the `_` digit separators from a decimal literal.
+// A literal can't start with a separator, since that would make it an identifier.
+fn remove_separators(number: &str) -> Option<String> {
+    if number.starts_with('_') {
+        return None;
+    }
+    Some(number.replace('_', ""))
+}
+
+// This function splits one of `suffixes` off the end of a numeric literal, leaving a non-empty number.
+fn split_suffix<'a>(
+    str_value: &'a str,
+    suffixes: &[&'static str],
+) -> Option<(&'a str, &'static str)> {
+    suffixes.iter().find_map(|suffix| {
+        str_value
+            .strip_suffix(suffix)
+            .filter(|number| !number.is_empty())
+            .map(|number| (number, *suffix))
+    })
+}
+
+// This function categorizes the text of a `proc_macro2::Literal` into exactly one `Token`.
+// It returns `None` if the literal looks like a known kind but cannot be decoded.
+fn convert_literal(str_value: &str) -> Option<Token> {
+    let tok = if let Some(int_value) = parse_integer(str_value) {
+        Token::Integer(int_value)
+    } else if is_integer_literal(str_value) {
+        // The digits are valid, so the integer can only have failed to parse because it overflowed.
+        Token::BigInteger(str_value.to_string())
+    } else if let Some(float_value) = parse_float(str_value) {
+        Token::Float(float_value)
+    } else if let Some((value, suffix)) = split_suffix(str_value, &INTEGER_SUFFIXES)
+        .and_then(|(number, suffix)| Some((parse_integer(number)?, suffix)))
+    {
+        Token::IntegerSuffixed {
+            value,
+            suffix: suffix.to_string(),
+        }
+    } else if let Some((digits, suffix)) =
+        split_suffix(str_value, &INTEGER_SUFFIXES).filter(|(number, _)| is_integer_literal(number))
+    {
+        // As with unsuffixed integers, valid digits can only have failed to parse because they overflowed.
+        Token::BigIntegerSuffixed {
+            digits: digits.to_string(),
+            suffix: suffix.into(),
+        }
+    } else if let Some((value, suffix)) = split_suffix(str_value, &FLOAT_SUFFIXES)
+        .and_then(|(number, suffix)| Some((parse_float(number)?, suffix)))
+    {
+        Token::FloatSuffixed {
+            value,
+            suffix: suffix.to_string(),
+        }
+    } else if let Some(inner) = str_value
+        .strip_prefix("b'")
+        .and_then(|inner| inner.strip_suffix('\''))
+    {
+        Token::ByteChar(unescape_byte(inner)?)
+    } else if let Some(as_char) = str_value
+        .strip_prefix('\'')
+        .and_then(|inner| inner.strip_suffix('\''))
+        .and_then(unescape_single_char)
+    {
+        Token::Char(as_char)
+    } else if let Some(content) = str_value
+        .strip_prefix('"')
+        .and_then(|inner| inner.strip_suffix('"'))
+    {
+        Token::String(content.to_string())
+    } else if let Some(content) = str_value
+        .strip_prefix("b\"")
+        .and_then(|inner| inner.strip_suffix('"'))
+    {
+        Token::ByteString(content.to_string())
+    } else if let Some((content, hashes)) = str_value.strip_prefix('r').and_then(strip_raw_quotes) {
+        Token::RawString {
+            content: content.to_string(),
+            hashes,
+        }
+    } else if let Some((content, hashes)) = str_value.strip_prefix("br").and_then(strip_raw_quotes)
+    {
+        Token::RawByteString {
+            content: content.to_string(),
+            hashes,
+        }
+    } else {
+        Token::Literal(str_value.to_string())
+    };
+    Some(tok)
+}
+
+// This function strips the hashes and quotes surrounding the contents of a raw string, such as `#"hello"#`.
+// It returns the contents along with the number of hashes that were on each side.
+fn strip_raw_quotes(raw: &str) -> Option<(&str, usize)> {
+    let hashes = raw.len() - raw.trim_start_matches('#').len();
+    let body = raw[hashes..].strip_prefix('"')?;
+    let content = body.strip_suffix(&raw[..hashes])?.strip_suffix('"')?;
+    Some((content, hashes))
+}
+
+// This function decodes the contents of a character literal, which must be exactly one (possibly escaped) character.
+fn unescape_single_char(inner: &str) -> Option<char> {
+    let mut chars = inner.chars();
+    let as_char = unescape_char(&mut chars)?;
+    if chars.next().is_some() {
+        return None;
+    }
+    Some(as_char)
+}
+
+// This function decodes the contents of a byte character literal, which must be exactly one (possibly escaped) ASCII character.
+// Unlike in character literals, `\x` escapes can go up to `\xFF`, and `\u{...}` escapes aren't allowed.
+fn unescape_byte(inner: &str) -> Option<u8> {
+    if let Some(hex) = inner.strip_prefix("\\x") {
+        if hex.len() != 2 || !hex.chars().all(|ch| ch.is_ascii_hexdigit()) {
+            return None;
+        }
+        return u8::from_str_radix(hex, 16).ok();
+    }
+    if inner.starts_with("\\u") {
+        return None;
+    }
+    let as_char = unescape_single_char(inner)?;
+    if !as_char.is_ascii() {
+        return None;
+    }
+    Some(as_char as u8)
+}
+
+// This function decodes every escape sequence in the contents of a string literal.
+fn unescape_string(content: &str) -> Option<String> {
+    let mut output = String::new();
+    let mut chars = content.chars();
+    loop {
+        let rest = chars.as_str();
+        if rest.is_empty() {
+            return Some(output);
+        }
+        // A backslash at the end of a line skips the newline and any leading whitespace on the next line.
+        if rest.starts_with("\\\n") || rest.starts_with("\\\r\n") {
+            chars = rest[1..]
+                .trim_start_matches(&[' ', '\t', '\n', '\r'][..])
+                .chars();
+            continue;
+        }
+        output.push(unescape_char(&mut chars)?);
+    }
+}
+
+// This function decodes every escape sequence in the contents of a byte string literal.
+// Each character or escape sequence is decoded with `unescape_byte`, so `\x` escapes can go up to `\xFF`.
+fn unescape_byte_string(content: &str) -> Option<Vec<u8>> {
+    let mut output = Vec::new();
+    let mut rest = content;
+    while !rest.is_empty() {
+        if rest.starts_with("\\\n") || rest.starts_with("\\\r\n") {
+            rest = rest[1..].trim_start_matches(&[' ', '\t', '\n', '\r'][..]);
+            continue;
+        }
+        let length = if rest.starts_with("\\x") {
+            4
+        } else if rest.starts_with('\\') {
+            2
+        } else {
+            rest.chars().next()?.len_utf8()
+        };
+        output.push(unescape_byte(rest.get(..length)?)?);
+        rest = &rest[length..];
+    }
+    Some(output)
+}
+
+// This function reads a single character from `chars`, decoding a Rust escape sequence if one starts there.
+// It returns `None` at the end of input or if the escape sequence is invalid.
+fn unescape_char(chars: &mut std::str::Chars) -> Option<char> {
+    match chars.next()? {
+        '\\' => match chars.next()? {
+            'n' => Some('\n'),
+            'r' => Some('\r'),
+            't' => Some('\t'),
+            '\\' => Some('\\'),
+            '0' => Some('\0'),
+            '\'' => Some('\''),
+            '"' => Some('"'),
+            'x' => {
+                let high = chars.next()?.to_digit(8)?;
+                let low = chars.next()?.to_digit(16)?;
+                char::from_u32(high * 16 + low)
+            }
+            'u' => {
+                if chars.next()? != '{' {
+                    return None;
+                }
+                let mut value: u32 = 0;
+                let mut digits = 0;
+                loop {
+                    match chars.next()? {
+                        '}' => break,
+                        '_' => {}
+                        digit => {
+                            value = value * 16 + digit.to_digit(16)?;
+                            digits += 1;
+                            if digits > 6 {
+                                return None;
+                            }
+                        }
+                    }
+                }
+                if digits == 0 {
+                    return None;
+                }
+                char::from_u32(value)
+            }
+            _ => None,
+        },
+        other => Some(other),
     }
 }